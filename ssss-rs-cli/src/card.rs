@@ -0,0 +1,388 @@
+//! Printable cards of shards, as self-contained HTML or PDF
+//!
+//! A card holds the QR code of a shard, its metadata and its text in short lines, each followed by
+//! a checksum that points out the line with a typo when the shard is typed back in.
+
+use crate::Encoding;
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use ssss_rs_core::SsssShard;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters of shard text per line of a card
+const LINE_LEN: usize = 16;
+
+/// Characters per group within a line
+const GROUP_LEN: usize = 4;
+
+/// Words per line of a card in the words encoding
+const WORDS_PER_LINE: usize = 4;
+
+/// Everything printed on a card
+pub(crate) struct Card {
+    pub(crate) number: u32,
+    pub(crate) set_id: Option<u32>,
+    pub(crate) threshold: Option<u32>,
+    pub(crate) total: Option<u32>,
+    pub(crate) integrity_check: bool,
+    pub(crate) date: String,
+    pub(crate) custodian: Option<String>,
+    pub(crate) encoding: Encoding,
+    pub(crate) text: String,
+}
+
+impl Card {
+    pub(crate) fn new(shard: &SsssShard, encoding: Encoding, date: String, custodian: Option<String>) -> Self {
+        Card {
+            number: shard.number(),
+            set_id: shard.set_id(),
+            threshold: shard.threshold(),
+            total: shard.num_shards(),
+            integrity_check: shard.has_checksum(),
+            date,
+            custodian,
+            encoding,
+            text: shard.to_string_with(encoding.codec().as_ref()),
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.total {
+            Some(total) => format!("Shard {} of {}", self.number, total),
+            None => format!("Shard {}", self.number),
+        }
+    }
+
+    /// Label and value pairs of the metadata
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![];
+        if let Some(id) = self.set_id {
+            fields.push(("Share set", format!("{id:08x}")));
+        }
+        if let (Some(threshold), Some(total)) = (self.threshold, self.total) {
+            fields.push(("Threshold", format!("{threshold} of {total}")));
+        }
+        fields.push(("Created", self.date.clone()));
+        if let Some(custodian) = &self.custodian {
+            fields.push(("Custodian", custodian.clone()));
+        }
+        if self.integrity_check {
+            fields.push(("Integrity check", "yes".into()));
+        }
+        fields
+    }
+
+    fn instructions(&self) -> Vec<String> {
+        let needed = match self.threshold {
+            Some(threshold) => format!("Any {threshold} shards of this share set restore the secret; fewer reveal nothing."),
+            None => "Enough shards of the same split restore the secret; fewer reveal nothing.".into(),
+        };
+        let merge = match self.encoding {
+            Encoding::Base64 => "ssss-rs-cli merge".to_string(),
+            encoding => format!("ssss-rs-cli merge --encoding {}", encoding.to_possible_value().expect("no encoding is skipped").get_name()),
+        };
+        let (typing, checksum) = match self.encoding {
+            Encoding::Words => ("with a single space between its lines and between words, leaving out the checksums.", "its line as printed"),
+            _ => ("leaving out the spaces and the checksums.", "the characters of its line, leaving out the spaces"),
+        };
+        vec![
+            needed,
+            format!("To recover, type each shard on one line into `{merge}`,"),
+            typing.into(),
+            format!("Or save scans of the QR codes as PNG images and run `{merge} --qr` on them."),
+            format!("A checksum is the first four hex digits of the SHA-256 of {checksum}; a mismatch marks a typo."),
+            "Keep this card safe and apart from the other shards.".into(),
+        ]
+    }
+}
+
+/// Splits the shard text in lines, each with its checksum: lines of groups of characters, or for
+/// the words encoding, the metadata and then lines of whole words
+pub(crate) fn chunk_lines(text: &str, encoding: Encoding) -> Vec<(String, String)> {
+    let checksum = |line: &str| {
+        let digest = Sha256::digest(line.as_bytes());
+        format!("{:02x}{:02x}", digest[0], digest[1])
+    };
+
+    if encoding == Encoding::Words {
+        let first_word = text.find(char::is_whitespace).unwrap_or(text.len());
+        let (metadata, words) = text.split_at(text[..first_word].rfind('-').map_or(0, |i| i + 1));
+        let words: Vec<&str> = words.split_whitespace().collect();
+        let lines = std::iter::once(metadata.to_string()).filter(|line| !line.is_empty()).chain(words.chunks(WORDS_PER_LINE).map(|line| line.join(" ")));
+        return lines.map(|line| (line.clone(), checksum(&line))).collect();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(LINE_LEN)
+        .map(|line| {
+            let grouped = line.chunks(GROUP_LEN).map(String::from_iter).collect::<Vec<_>>().join(" ");
+            (grouped, checksum(&String::from_iter(line)))
+        })
+        .collect()
+}
+
+/// Today's date in UTC, as `YYYY-MM-DD`
+pub(crate) fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400) as i64;
+    civil_date(days)
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// One HTML document with a card per printed page
+pub(crate) fn html(cards: &[Card]) -> Result<String, String> {
+    let mut output = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Shard cards</title>\n<style>\n",
+        "body { font-family: sans-serif; }\n",
+        ".card { border: 1px solid #000; padding: 1em; margin: 1em auto; max-width: 44em; page-break-after: always; break-after: page; }\n",
+        ".qr { float: left; width: 16em; margin-right: 1em; }\n",
+        "table.text { font-family: monospace; font-size: 1.1em; border-collapse: collapse; }\n",
+        "table.text td.check { color: #666; padding-left: 2em; }\n",
+        ".instructions { clear: both; font-size: 0.85em; padding-top: 1em; }\n",
+        "</style>\n</head>\n<body>\n"
+    ));
+
+    for card in cards {
+        let code = crate::qr::code(&card.text)?;
+        output.push_str("<section class=\"card\">\n");
+        output.push_str(&format!("<div class=\"qr\">{}</div>\n", crate::qr::svg(&code, &[])));
+        output.push_str(&format!("<h1>{}</h1>\n<dl>\n", escape_html(&card.title())));
+        for (label, value) in card.fields() {
+            output.push_str(&format!("<dt>{label}</dt><dd>{}</dd>\n", escape_html(&value)));
+        }
+        output.push_str("</dl>\n<table class=\"text\">\n");
+        for (line, check) in chunk_lines(&card.text, card.encoding) {
+            output.push_str(&format!("<tr><td>{}</td><td class=\"check\">{check}</td></tr>\n", escape_html(&line)));
+        }
+        output.push_str("</table>\n<div class=\"instructions\">\n");
+        for line in card.instructions() {
+            output.push_str(&format!("<p>{}</p>\n", escape_html(&line)));
+        }
+        output.push_str("</div>\n</section>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+    Ok(output)
+}
+
+/// A5 landscape, in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 420.0;
+const MARGIN: f64 = 28.0;
+const QR_SIDE: f64 = 200.0;
+
+/// Escapes a PDF string; anything but printable ASCII becomes `?`
+fn pdf_string(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{c}"),
+            ' '..='~' => c.to_string(),
+            _ => "?".into(),
+        })
+        .collect()
+}
+
+/// Draws one card, returning the content stream of its page
+fn pdf_page(card: &Card) -> Result<String, String> {
+    let code = crate::qr::code(&card.text)?;
+    let width = code.width();
+    let module = QR_SIDE / width as f64;
+    let top = PAGE_HEIGHT - MARGIN;
+
+    let mut content = String::from("0 g\n");
+    for y in 0..width {
+        for x in (0..width).filter(|&x| code[(x, y)] == qrcode::Color::Dark) {
+            content.push_str(&format!(
+                "{:.3} {:.3} {module:.3} {module:.3} re\n",
+                MARGIN + x as f64 * module,
+                top - (y + 1) as f64 * module
+            ));
+        }
+    }
+    content.push_str("f\n");
+
+    let mut text = |font: &str, size: f64, x: f64, y: f64, line: &str| {
+        content.push_str(&format!("BT /{font} {size} Tf {x:.1} {y:.1} Td ({}) Tj ET\n", pdf_string(line)));
+    };
+
+    let left = MARGIN + QR_SIDE + 20.0;
+    let mut y = top - 18.0;
+    text("F1", 18.0, left, y, &card.title());
+    y -= 26.0;
+    for (label, value) in card.fields() {
+        text("F1", 10.0, left, y, &format!("{label}: {value}"));
+        y -= 14.0;
+    }
+    y -= 8.0;
+    // Courier is 0.6 em wide, so the checksums go clear of the longest line
+    let lines = chunk_lines(&card.text, card.encoding);
+    let longest = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    let check_left = left + (longest as f64 * 0.6 * 11.0 + 12.0).max(150.0);
+    for (line, check) in lines {
+        text("F2", 11.0, left, y, &line);
+        text("F2", 9.0, check_left, y, &check);
+        y -= 14.0;
+    }
+
+    let mut y = (top - QR_SIDE - 24.0).min(y - 10.0);
+    for line in card.instructions() {
+        text("F1", 8.0, MARGIN, y, &line);
+        y -= 11.0;
+    }
+    if y < MARGIN / 2.0 {
+        return Err(format!("Shard {} is too long for a card", card.number));
+    }
+
+    Ok(content)
+}
+
+/// One PDF document with a page per card, using only the standard fonts
+pub(crate) fn pdf(cards: &[Card]) -> Result<Vec<u8>, String> {
+    // 1: catalog, 2: pages, 3 and 4: fonts, then a page and its content per card
+    let page_ids: Vec<usize> = (0..cards.len()).map(|i| 5 + 2 * i).collect();
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), cards.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (card, id) in cards.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            id + 1
+        ));
+        let content = pdf_page(card)?;
+        objects.push(format!("<< /Length {} >>\nstream\n{content}endstream", content.len()));
+    }
+
+    let mut output = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(output.len());
+        output.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+
+    let xref = output.len();
+    output.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        output.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    output.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1).as_bytes(),
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(text: &str) -> Card {
+        Card {
+            number: 2,
+            set_id: Some(0xc0ffee),
+            threshold: Some(3),
+            total: Some(5),
+            integrity_check: true,
+            date: "2024-02-29".into(),
+            custodian: Some("A & B <Trust>".into()),
+            encoding: Encoding::Base64,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn lines_and_checksums() {
+        let lines = chunk_lines("v2-00c0ffee-01-3-5-2-QUJDQQ==", Encoding::Base64);
+
+        assert_eq!(vec!["v2-0 0c0f fee- 01-3", "-5-2 -QUJ DQQ= ="], lines.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>());
+        let digest = Sha256::digest(b"v2-00c0ffee-01-3");
+        assert_eq!(format!("{:02x}{:02x}", digest[0], digest[1]), lines[0].1);
+    }
+
+    #[test]
+    fn words_card() {
+        let codec = Encoding::Words.codec();
+        let shard = SsssShard::parse_with("v2-00c0ffee-01-3-5-2-QUJDRA==", &ssss_rs_core::encoding::Base64).unwrap();
+        let card = Card::new(&shard, Encoding::Words, "2024-02-29".into(), None);
+        let lines = chunk_lines(&card.text, Encoding::Words);
+
+        assert_eq!("v2-00c0ffee-01-3-5-2-", lines[0].0);
+        assert!(lines[1..].iter().all(|(line, _)| line.split(' ').count() <= WORDS_PER_LINE));
+        for (line, check) in &lines {
+            let digest = Sha256::digest(line.as_bytes());
+            assert_eq!(&format!("{:02x}{:02x}", digest[0], digest[1]), check);
+        }
+
+        // Typed back as the instructions say, the lines give the shard
+        let typed = lines.iter().map(|(line, _)| line.as_str()).collect::<Vec<_>>().join(" ");
+        assert_eq!(shard.to_string(), SsssShard::parse_with(&typed, codec.as_ref()).unwrap().to_string());
+        let instructions = card.instructions().join("\n");
+        assert!(instructions.contains("`ssss-rs-cli merge --encoding words`"));
+        assert!(!instructions.contains("leaving out the spaces"));
+        assert!(pdf(&[card]).is_ok());
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!("1970-01-01", civil_date(0));
+        assert_eq!("2000-03-01", civil_date(11_017));
+        assert_eq!("2024-02-29", civil_date(19_782));
+        assert_eq!("1969-12-31", civil_date(-1));
+    }
+
+    #[test]
+    fn html_card() {
+        let html = html(&[card("v2-00c0ffee-01-3-5-2-QUJDQQ==")]).unwrap();
+
+        assert_eq!(1, html.matches("<section class=\"card\">").count());
+        assert!(html.contains("<h1>Shard 2 of 5</h1>"));
+        assert!(html.contains("<dd>00c0ffee</dd>"));
+        assert!(html.contains("<dd>3 of 5</dd>"));
+        assert!(html.contains("<dd>A &amp; B &lt;Trust&gt;</dd>"));
+        assert!(html.contains("<svg"));
+    }
+
+    #[test]
+    fn pdf_cross_references() {
+        let pdf = pdf(&[card("v2-00c0ffee-01-3-5-2-QUJDQQ=="), card("v2-00c0ffee-01-3-5-3-QUJDQg==")]).unwrap();
+        let text = String::from_utf8(pdf).unwrap();
+
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("(Custodian: A & B <Trust>) Tj"));
+
+        let (_, xref) = text.rsplit_once("startxref\n").unwrap();
+        let xref: usize = xref.trim_end_matches("\n%%EOF\n").parse().unwrap();
+        let offsets = text[xref..].lines().skip(3).take_while(|line| line.ends_with(" n "));
+        for (i, offset) in offsets.enumerate() {
+            let offset: usize = offset[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+
+    #[test]
+    fn pdf_strings() {
+        assert_eq!("a\\(b\\)\\\\ ?", pdf_string("a(b)\\ é"));
+    }
+}
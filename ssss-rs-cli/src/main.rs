@@ -1,20 +1,171 @@
+mod card;
 mod inputoutput;
+mod qr;
 
-use std::io::BufRead;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
 use inputoutput::InputOutput;
 
+/// Textual layout of the shards
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    /// This tool's own versioned shards
+    Native,
+    /// Shares of B. Poettering's `ssss-split` and `ssss-combine`
+    Ssss,
+    /// Unseal and recovery key shares of HashiCorp Vault
+    Vault,
+}
+
+/// How the data of native shards is written out
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Base64,
+    /// Unpadded base64 with `-` and `_`, safe in URLs and file names
+    Base64url,
+    /// Padded RFC 4648 base32, for case-insensitive media
+    Base32,
+    /// Unpadded base32 with an alphabet that is easy to read
+    Zbase32,
+    Hex,
+    /// Bech32m with the prefix `ssss1`, which detects typos
+    Bech32m,
+    /// Words with a checksum word, easier to read aloud or copy by hand
+    Words,
+}
+
+impl Encoding {
+    fn codec(self) -> Box<dyn ssss_rs_core::encoding::Encoding> {
+        use ssss_rs_core::encoding as e;
+        match self {
+            Encoding::Base64 => Box::new(e::Base64),
+            Encoding::Base64url => Box::new(e::Base64Url),
+            Encoding::Base32 => Box::new(e::Base32),
+            Encoding::Zbase32 => Box::new(e::ZBase32),
+            Encoding::Hex => Box::new(e::Hex),
+            Encoding::Bech32m => Box::new(e::Bech32m::default()),
+            Encoding::Words => Box::new(e::Words),
+        }
+    }
+}
+
+/// How `--qr` draws every shard
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum QrFormat {
+    /// Coloured blocks in the terminal, written to the output
+    Ansi,
+    /// A `<PREFIX>-<number>.svg` file per shard
+    Svg,
+    /// A `<PREFIX>-<number>.png` file per shard, which `merge --qr` reads back
+    Png,
+}
+
+/// Document format of `print`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum CardFormat {
+    Html,
+    Pdf,
+}
+
 #[derive(clap::Parser, Debug)]
 enum Action {
     /// Creates secret-shards of the given input
     Shard {
         #[arg(short, long)]
-        threshold: u8,
+        threshold: u32,
 
+        /// More than 255 shards are computed over GF(2^16), more than 65535 over GF(2^32); the
+        /// ssss and vault formats and --split-to allow at most 255
         #[arg(short, long = "number")]
-        number_of_shards: u8,
+        number_of_shards: u32,
+
+        /// Share a digest of the secret too, so merging detects wrong or too few shards
+        #[arg(long)]
+        integrity_check: bool,
+
+        /// Encrypt the input into this file, and only shard the key
+        #[arg(long, value_name = "CIPHERTEXT")]
+        hybrid: Option<PathBuf>,
+
+        /// Stream each shard into its own file `<PREFIX>.<number>`, for secrets of any size
+        #[arg(long, value_name = "PREFIX", conflicts_with = "hybrid")]
+        split_to: Option<PathBuf>,
+
+        /// Layout of the shards written
+        #[arg(long, value_enum, default_value = "native")]
+        format: Format,
+
+        #[arg(long, value_enum, default_value = "base64", conflicts_with = "split_to")]
+        encoding: Encoding,
+
+        /// Write one JSON document describing the split, rather than a shard per line
+        #[arg(long, conflicts_with = "split_to")]
+        json: bool,
+
+        /// Draw every shard as a QR code along with its text and metadata, for paper backups
+        #[arg(long, value_enum, conflicts_with_all = ["split_to", "json"])]
+        qr: Option<QrFormat>,
+
+        /// File name prefix of the `--qr svg` and `--qr png` images
+        #[arg(long, value_name = "PREFIX", default_value = "shard", requires = "qr")]
+        qr_prefix: PathBuf,
+
+        /// Prefix every `ssss` share with this token
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Skip the diffusion layer of the `ssss` format, like `ssss-split -D`
+        #[arg(long)]
+        no_diffusion: bool,
+    },
+    /// Merges shards back together. Fails when fewer shards than the threshold are given.
+    Merge {
+        /// Correct corrupted shards, given more shards than the threshold; only for splits of at
+        /// most 255 shards
+        #[arg(long)]
+        robust: bool,
+
+        /// Decrypt this file, created by `shard --hybrid`, with the merged key
+        #[arg(long)]
+        ciphertext: Option<PathBuf>,
+
+        /// Stream the secret from these shard files, as created by `shard --split-to`
+        #[arg(conflicts_with_all = ["robust", "ciphertext"])]
+        shard_files: Vec<PathBuf>,
+
+        /// Layout of the shards read; give exactly the threshold number of `ssss` shares, as they do not record it
+        #[arg(long, value_enum, default_value = "native")]
+        format: Format,
+
+        #[arg(long, value_enum, default_value = "base64", conflicts_with = "shard_files")]
+        encoding: Encoding,
+
+        /// Read the shards from PNG images of their QR codes, such as those written by `shard --qr png`
+        /// or scans of a printed card, rather than the input
+        #[arg(long, value_name = "PNG", num_args = 1.., conflicts_with = "shard_files")]
+        qr: Vec<PathBuf>,
+
+        /// The `ssss` shares were made without diffusion, like `ssss-combine -D`
+        #[arg(long)]
+        no_diffusion: bool,
+    },
+    /// Lays out a printable card per shard, with its QR code, metadata and recovery instructions
+    Print {
+        #[arg(long, value_enum, default_value = "html")]
+        format: CardFormat,
+
+        /// Custodian named on each card, given once per shard in the order of the input
+        #[arg(long)]
+        custodian: Vec<String>,
+
+        /// Creation date printed on the cards, today if not given
+        #[arg(long)]
+        date: Option<String>,
+
+        #[arg(long, value_enum, default_value = "base64")]
+        encoding: Encoding,
     },
-    /// Merges shards back together. When the threshold is reached, sensible output is given.
-    Merge
 }
 
 #[derive(clap::Parser, Debug)]
@@ -25,18 +176,24 @@ struct SsssRsOpt {
 
     #[command(flatten)]
     io: InputOutput,
+
+    /// Threads to split and merge large secrets with, all cores if not present
+    #[arg(short, long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
 }
 
 fn main() -> Result<(), String> {
     use clap::Parser;
     let opt = SsssRsOpt::parse();
 
-    let result = match opt.action {
-        Action::Shard { threshold, number_of_shards } => create_shards(threshold, number_of_shards, &opt.io),
-        Action::Merge => merge_shards(&opt.io),
-    };
+    if let Some(jobs) = opt.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs as usize)
+            .build_global()
+            .map_err(|e| format!("Could not start {jobs} threads: {e}"))?;
+    }
 
-    if let Err(message) = result {
+    if let Err(message) = run(opt) {
         println!("Unsuccessful execution of program:");
         println!("{}", message);
     };
@@ -44,41 +201,405 @@ fn main() -> Result<(), String> {
     Ok(())
 }
 
-fn merge_shards(io: &InputOutput) -> Result<(), String> {
+fn run(opt: SsssRsOpt) -> Result<(), String> {
+    match opt.action {
+        Action::Shard { format, integrity_check, hybrid, split_to, encoding, json, qr, .. }
+            if format != Format::Native
+                && (integrity_check || hybrid.is_some() || split_to.is_some() || encoding != Encoding::Base64 || json || qr.is_some()) =>
+        {
+            Err("Only the native format supports --integrity-check, --hybrid, --split-to, --encoding, --json and --qr".into())
+        }
+        Action::Shard { format, token, no_diffusion, .. } if format != Format::Ssss && (token.is_some() || no_diffusion) => {
+            Err("Only the ssss format supports --token and --no-diffusion".into())
+        }
+        Action::Shard { threshold, number_of_shards, format: Format::Ssss, token, no_diffusion, .. } => {
+            create_classic_shards(threshold, number_of_shards, token.as_deref(), no_diffusion, &opt.io)
+        }
+        Action::Shard { threshold, number_of_shards, format: Format::Vault, .. } => {
+            create_vault_shards(threshold, number_of_shards, &opt.io)
+        }
+        Action::Merge { format, robust, ciphertext, shard_files, encoding, qr, .. }
+            if format != Format::Native
+                && (robust || ciphertext.is_some() || !shard_files.is_empty() || encoding != Encoding::Base64 || !qr.is_empty()) =>
+        {
+            Err("Only the native format supports --robust, --ciphertext, --encoding, --qr and shard files".into())
+        }
+        Action::Merge { format, no_diffusion: true, .. } if format != Format::Ssss => {
+            Err("Only the ssss format supports --no-diffusion".into())
+        }
+        Action::Merge { format: Format::Ssss, no_diffusion, .. } => merge_classic_shards(no_diffusion, &opt.io),
+        Action::Merge { format: Format::Vault, .. } => merge_vault_shards(&opt.io),
+        Action::Shard { threshold, number_of_shards, integrity_check, split_to: Some(prefix), .. } => {
+            stream_shards(threshold, number_of_shards, integrity_check, &prefix, &opt.io)
+        }
+        Action::Shard { threshold, number_of_shards, integrity_check, hybrid, split_to: None, encoding, json, qr, qr_prefix, .. } => {
+            let output = match (json, qr) {
+                (true, _) => ShardOutput::Json,
+                (false, Some(format)) => ShardOutput::Qr(format, &qr_prefix),
+                (false, None) => ShardOutput::Lines,
+            };
+            create_shards(threshold, number_of_shards, integrity_check, hybrid.as_deref(), encoding, output, &opt.io)
+        }
+        Action::Merge { shard_files, .. } if !shard_files.is_empty() => stream_merge(&shard_files, &opt.io),
+        Action::Print { format, custodian, date, encoding } => print_cards(format, &custodian, date, encoding, &opt.io),
+        Action::Merge { robust, ciphertext, encoding, qr, .. } => merge_shards(robust, ciphertext.as_deref(), encoding, &qr, &opt.io),
+    }
+}
+
+fn merge_shards(robust: bool, ciphertext: Option<&Path>, encoding: Encoding, qr_images: &[PathBuf], io: &InputOutput) -> Result<(), String> {
     let mut shards = Vec::<ssss_rs_core::SsssShard>::new();
-    let input = io.get_input()?;
+    let codec = encoding.codec();
 
-    let mut reader = std::io::BufReader::new(input);
+    for path in qr_images {
+        let image = std::fs::read(path).map_err(|_| format!("Could not read image {}!", path.display()))?;
+        let shard = qr::read_png(&image)
+            .and_then(|text| ssss_rs_core::SsssShard::parse_with(&text, codec.as_ref()).map_err(|x| x.to_string()))
+            .map_err(|x| format!("{} in {}", x, path.display()))?;
+        report_progress(&shard, shards.len() + 1);
+        shards.push(shard);
+    }
+    let input: Box<dyn std::io::Read> = if qr_images.is_empty() { io.get_input()? } else { Box::new(std::io::empty()) };
+
+    let mut reader = BufReader::new(input);
     let mut input_buffer = String::new();
     let mut line = 1;
     while reader.read_line(&mut input_buffer).is_ok() {
         if input_buffer.is_empty() { break; }
-        shards.push(input_buffer.trim().parse().map_err(|x| format!("{} on line {}", x, line))?);
+        let shard = ssss_rs_core::SsssShard::parse_with(&input_buffer, codec.as_ref())
+            .map_err(|x| format!("{} on line {}", x, line))?;
+        report_progress(&shard, shards.len() + 1);
+        shards.push(shard);
         input_buffer.clear();
         line += 1;
     }
 
-    let secret = ssss_rs_core::decode(shards.as_slice());
+    let secret = if robust {
+        let decoded = ssss_rs_core::decode_robust(shards.as_slice()).map_err(|x| x.to_string())?;
+        if !decoded.faulty_shards.is_empty() {
+            eprintln!("Corrected faulty shards {:?}", decoded.faulty_shards);
+        }
+        decoded.secret
+    } else {
+        ssss_rs_core::decode(shards.as_slice()).map_err(|x| x.to_string())?
+    };
 
     let mut out = io.get_output()?;
+    match ciphertext {
+        // The decrypted file is written as is, like `stream_merge` does
+        Some(path) => {
+            let ciphertext = std::fs::read(path).map_err::<String, _>(|_| "Could not read ciphertext!".into())?;
+            let plaintext = ssss_rs_core::hybrid::decrypt(&secret, &ciphertext).map_err(|x| x.to_string())?;
+            out.write_all(plaintext.as_slice()).map_err(|_| "Could not write output!")?;
+        }
+        None => {
+            out.write_all(secret.as_slice()).map_err(|_| "Could not write output!")?;
+            writeln!(out).map_err::<String, _>(|_| "Could not write output!".into())?;
+        }
+    }
+    Ok(())
+}
+
+fn print_cards(format: CardFormat, custodians: &[String], date: Option<String>, encoding: Encoding, io: &InputOutput) -> Result<(), String> {
+    let codec = encoding.codec();
+    let reader = BufReader::new(io.get_input()?);
+    let shards = reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| ssss_rs_core::SsssShard::parse_with(&line, codec.as_ref()).map_err(|x| format!("{} on line {}", x, i + 1)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if !custodians.is_empty() && custodians.len() != shards.len() {
+        return Err(format!("Got {} custodians for {} shards", custodians.len(), shards.len()));
+    }
+
+    let date = date.unwrap_or_else(card::today);
+    let cards: Vec<card::Card> = shards
+        .iter()
+        .enumerate()
+        .map(|(i, shard)| card::Card::new(shard, encoding, date.clone(), custodians.get(i).cloned()))
+        .collect();
+
+    let document = match format {
+        CardFormat::Html => card::html(&cards)?.into_bytes(),
+        CardFormat::Pdf => card::pdf(&cards)?,
+    };
+    io.get_output()?.write_all(&document).map_err(|_| "Could not write output!".into())
+}
 
+fn merge_classic_shards(no_diffusion: bool, io: &InputOutput) -> Result<(), String> {
+    use ssss_rs_core::compat::ssss;
+
+    let reader = BufReader::new(io.get_input()?);
+    let shards = reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| line.trim().parse::<ssss::ClassicShard>().map_err(|x| format!("{} on line {}", x, i + 1)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let secret = ssss::decode(&shards, !no_diffusion).map_err(|x| x.to_string())?;
+
+    let mut out = io.get_output()?;
+    out.write_all(secret.as_slice()).map_err(|_| "Could not write output!")?;
+    writeln!(out).map_err::<String, _>(|_| "Could not write output!".into())?;
+    Ok(())
+}
+
+fn merge_vault_shards(io: &InputOutput) -> Result<(), String> {
+    use ssss_rs_core::compat::vault;
+
+    let reader = BufReader::new(io.get_input()?);
+    let shards = reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| line.trim().parse::<vault::VaultShard>().map_err(|x| format!("{} on line {}", x, i + 1)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let secret = vault::decode(&shards).map_err(|x| x.to_string())?;
+
+    let mut out = io.get_output()?;
     out.write_all(secret.as_slice()).map_err(|_| "Could not write output!")?;
-    writeln!(out, "").map_err::<String, _>(|_| "Could not write output!".into())?;
+    writeln!(out).map_err::<String, _>(|_| "Could not write output!".into())?;
     Ok(())
 }
 
-fn create_shards(thresh: u8, num: u8, io: &InputOutput) -> Result<(), String> {
+fn stream_merge(shard_files: &[PathBuf], io: &InputOutput) -> Result<(), String> {
+    let mut inputs = shard_files
+        .iter()
+        .map(|path| {
+            File::open(path)
+                .map(BufReader::new)
+                .map_err(|_| format!("Could not open shard file {}!", path.display()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let output = BufWriter::new(io.get_output()?);
+    ssss_rs_core::stream::decode_stream(&mut inputs, output).map_err(|x| x.to_string())
+}
+
+fn report_progress(shard: &ssss_rs_core::SsssShard, have: usize) {
+    match shard.threshold() {
+        Some(threshold) => eprintln!("Read shard {} (have {}, need {})", shard.number(), have, threshold),
+        None => eprintln!("Read shard {} ({} so far)", shard.number(), have),
+    }
+}
+
+fn stream_shards(thresh: u32, num: u32, integrity_check: bool, prefix: &Path, io: &InputOutput) -> Result<(), String> {
+    let mut options = ssss_rs_core::ShamirScheme::new(thresh, num);
+    if integrity_check {
+        options = options.with_integrity_check();
+    }
+    // Checked before creating any file
+    if options.field() != ssss_rs_core::FieldSize::Gf8 {
+        return Err("--split-to allows at most 255 shards".into());
+    }
+
+    let mut outputs = (1..=num)
+        .map(|n| {
+            let mut path = prefix.as_os_str().to_owned();
+            path.push(format!(".{n}"));
+            File::create(&path)
+                .map(BufWriter::new)
+                .map_err(|_| format!("Could not create shard file {}!", Path::new(&path).display()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let input = BufReader::new(io.get_input()?);
+    ssss_rs_core::stream::encode_stream(&options, input, &mut outputs).map_err(|x| x.to_string())
+}
+
+/// What `create_shards` writes
+enum ShardOutput<'a> {
+    /// A shard per line
+    Lines,
+    /// A JSON document describing the split
+    Json,
+    /// QR codes, images named after the prefix
+    Qr(QrFormat, &'a Path),
+}
+
+/// The document written by `shard --json`
+#[derive(serde::Serialize)]
+struct SplitDocument<'a> {
+    scheme: &'a ssss_rs_core::ShamirScheme,
+    set_id: Option<String>,
+    ciphertext: Option<&'a Path>,
+    shards: Vec<String>,
+}
+
+fn create_shards(thresh: u32, num: u32, integrity_check: bool, hybrid: Option<&Path>, encoding: Encoding, output: ShardOutput, io: &InputOutput) -> Result<(), String> {
 let mut input_buffer = Vec::new();
 
     io.get_input()?.read_to_end(&mut input_buffer).map_err::<String, _>(|_| "Could not read input!".into())?;
 
-    let options = ssss_rs_core::ShamirScheme::new(thresh, num);
-    let shards = ssss_rs_core::encode(&options, input_buffer.as_slice());
+    let mut options = ssss_rs_core::ShamirScheme::new(thresh, num);
+    if integrity_check {
+        options = options.with_integrity_check();
+    }
+    let shards = match hybrid {
+        Some(path) => {
+            let split = ssss_rs_core::hybrid::encode(&options, input_buffer.as_slice()).map_err(|x| x.to_string())?;
+            std::fs::write(path, &split.ciphertext).map_err::<String, _>(|_| "Could not write ciphertext!".into())?;
+            split.key_shards
+        }
+        None => ssss_rs_core::encode(&options, input_buffer.as_slice()).map_err(|x| x.to_string())?,
+    };
+    let codec = encoding.codec();
+    let mut out = io.get_output()?;
+
+    if let ShardOutput::Json = output {
+        let document = SplitDocument {
+            scheme: &options,
+            set_id: shards[0].set_id().map(|id| format!("{id:08x}")),
+            ciphertext: hybrid,
+            shards: shards.iter().map(|shard| shard.to_string_with(codec.as_ref())).collect(),
+        };
+        serde_json::to_writer_pretty(&mut out, &document).map_err(|x| x.to_string())?;
+        return writeln!(out).map_err(|_| "Could not write output!".into());
+    }
+
+    for shard in shards {
+        let text = shard.to_string_with(codec.as_ref());
+        let ShardOutput::Qr(format, prefix) = output else {
+            writeln!(out, "{}", text).map_err::<String, _>(|_| "Could not write output!".into())?;
+            continue;
+        };
+
+        let code = qr::code(&text)?;
+        let caption = [text, describe(&shard)];
+        let (image, extension) = match format {
+            QrFormat::Ansi => {
+                write!(out, "{}", qr::ansi(&code, &caption)).map_err::<String, _>(|_| "Could not write output!".into())?;
+                continue;
+            }
+            QrFormat::Svg => (qr::svg(&code, &caption).into_bytes(), "svg"),
+            QrFormat::Png => (qr::png(&code, &caption)?, "png"),
+        };
+
+        let mut path = prefix.as_os_str().to_owned();
+        path.push(format!("-{}.{extension}", shard.number()));
+        std::fs::write(&path, image).map_err(|_| format!("Could not write image {}!", Path::new(&path).display()))?;
+        writeln!(out, "{}", caption[0]).map_err::<String, _>(|_| "Could not write output!".into())?;
+    }
+
+    Ok(())
+}
+
+/// A line of metadata printed below the QR code of a shard
+fn describe(shard: &ssss_rs_core::SsssShard) -> String {
+    let mut description = match (shard.threshold(), shard.num_shards()) {
+        (Some(threshold), Some(total)) => format!("Shard {} of {}, {} needed", shard.number(), total, threshold),
+        _ => format!("Shard {}", shard.number()),
+    };
+    if let Some(id) = shard.set_id() {
+        description.push_str(&format!(", set {id:08x}"));
+    }
+    if shard.has_checksum() {
+        description.push_str(", integrity checked");
+    }
+    description
+}
+
+/// The threshold and number of shards for a format that numbers shards with a single byte
+fn byte_sized(thresh: u32, num: u32, format: &str) -> Result<(u8, u8), String> {
+    match (u8::try_from(thresh), u8::try_from(num)) {
+        (Ok(thresh), Ok(num)) => Ok((thresh, num)),
+        _ => Err(format!("The {format} format allows at most 255 shards")),
+    }
+}
+
+fn create_classic_shards(thresh: u32, num: u32, token: Option<&str>, no_diffusion: bool, io: &InputOutput) -> Result<(), String> {
+    use ssss_rs_core::compat::ssss;
+
+    let mut input_buffer = Vec::new();
+    io.get_input()?.read_to_end(&mut input_buffer).map_err::<String, _>(|_| "Could not read input!".into())?;
+    // `ssss-split` reads the secret as a single line
+    if input_buffer.ends_with(b"\n") {
+        input_buffer.pop();
+    }
+
+    let (thresh, num) = byte_sized(thresh, num, "ssss")?;
+    let mut options = ssss::ClassicScheme::new(thresh, num);
+    if let Some(token) = token {
+        options = options.with_token(token);
+    }
+    if no_diffusion {
+        options = options.without_diffusion();
+    }
+    let shards = ssss::encode(&options, &input_buffer).map_err(|x| x.to_string())?;
+
     let mut out = io.get_output()?;
+    for shard in shards {
+        writeln!(out, "{}", shard).map_err::<String, _>(|_| "Could not write output!".into())?;
+    }
+
+    Ok(())
+}
+
+fn create_vault_shards(thresh: u32, num: u32, io: &InputOutput) -> Result<(), String> {
+    use ssss_rs_core::compat::vault;
+
+    let mut input_buffer = Vec::new();
+    io.get_input()?.read_to_end(&mut input_buffer).map_err::<String, _>(|_| "Could not read input!".into())?;
 
+    let (thresh, num) = byte_sized(thresh, num, "vault")?;
+    let shards = vault::encode(&vault::VaultScheme::new(thresh, num), &input_buffer).map_err(|x| x.to_string())?;
+
+    let mut out = io.get_output()?;
     for shard in shards {
         writeln!(out, "{}", shard).map_err::<String, _>(|_| "Could not write output!".into())?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// A fresh directory for the files of one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ssss-rs-cli-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run_args(args: &[&Path]) -> Result<(), String> {
+        run(SsssRsOpt::parse_from([Path::new("ssss-rs")].iter().chain(args)))
+    }
+
+    #[test]
+    fn hybrid_round_trip_is_byte_exact() {
+        let dir = scratch_dir("hybrid");
+        let (input, shards, ciphertext, output) =
+            (dir.join("input"), dir.join("shards"), dir.join("ciphertext"), dir.join("output"));
+        let payload: Vec<u8> = (0..=255).chain([b'\n', 0, b'\n']).collect();
+        std::fs::write(&input, &payload).unwrap();
+
+        let arg = Path::new;
+        run_args(&[arg("-i"), &input, arg("-o"), &shards, arg("shard"), arg("-t"), arg("2"), arg("-n"), arg("3"), arg("--hybrid"), &ciphertext]).unwrap();
+        run_args(&[arg("-i"), &shards, arg("-o"), &output, arg("merge"), arg("--ciphertext"), &ciphertext]).unwrap();
+
+        assert_eq!(payload, std::fs::read(&output).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ssss_flags_need_the_ssss_format() {
+        let arg = Path::new;
+        let shard = Err("Only the ssss format supports --token and --no-diffusion".to_string());
+        let merge = Err("Only the ssss format supports --no-diffusion".to_string());
+
+        assert_eq!(shard, run_args(&[arg("shard"), arg("-t"), arg("2"), arg("-n"), arg("3"), arg("--token"), arg("x")]));
+        assert_eq!(shard, run_args(&[arg("shard"), arg("-t"), arg("2"), arg("-n"), arg("3"), arg("--no-diffusion")]));
+        assert_eq!(merge, run_args(&[arg("merge"), arg("--no-diffusion")]));
+        assert_eq!(shard, run_args(&[arg("shard"), arg("-t"), arg("2"), arg("-n"), arg("3"), arg("--format"), arg("vault"), arg("--token"), arg("x")]));
+        assert_eq!(merge, run_args(&[arg("merge"), arg("--format"), arg("vault"), arg("--no-diffusion")]));
+    }
+}
@@ -0,0 +1,307 @@
+//! Reading the text of a QR symbol from its modules
+//!
+//! Format information is matched to the nearest of the valid patterns, and every block is
+//! corrected up to half its error correction codewords, so a sampled grid with a few wrong
+//! modules still reads back.
+
+use qrcode::canvas::{Canvas, MaskPattern, Module};
+use qrcode::types::{Color, EcLevel, Mode, Version};
+
+/// The alphabet of the alphanumeric mode, in order of value
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+const MASKS: [MaskPattern; 8] = [
+    MaskPattern::Checkerboard,
+    MaskPattern::HorizontalLines,
+    MaskPattern::VerticalLines,
+    MaskPattern::DiagonalLines,
+    MaskPattern::LargeCheckerboard,
+    MaskPattern::Fields,
+    MaskPattern::Diamonds,
+    MaskPattern::Meadow,
+];
+
+/// Decodes the text of a symbol given as rows of modules, dark being `true`
+pub(super) fn decode_modules(grid: &[bool], width: usize) -> Result<String, String> {
+    let version = Version::Normal(((width - 17) / 4) as i16);
+    let mut canvas = Canvas::new(version, EcLevel::L);
+    canvas.draw_all_functional_patterns();
+    let functional: Vec<bool> = (0..width * width)
+        .map(|i| canvas.get((i % width) as i16, (i / width) as i16) != Module::Empty)
+        .collect();
+
+    // Drawing the function patterns, format information included, for every error correction
+    // level and mask, the one closest to the image tells both; its data modules are the bare mask
+    let (_, ec_level, mask) = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H]
+        .into_iter()
+        .flat_map(|ec_level| MASKS.map(|mask| (ec_level, mask)))
+        .map(|(ec_level, mask)| {
+            let mut canvas = Canvas::new(version, ec_level);
+            canvas.draw_all_functional_patterns();
+            canvas.apply_mask(mask);
+            let colors = canvas.into_colors();
+            let distance = (0..width * width)
+                .filter(|&i| functional[i] && (colors[i] == Color::Dark) != grid[i])
+                .count();
+            (distance, ec_level, colors)
+        })
+        .min_by_key(|(distance, ..)| *distance)
+        .expect("there are candidates");
+
+    // Codewords run in pairs of columns from the right, alternately up and down, skipping the
+    // vertical timing pattern
+    let mut bits = Vec::new();
+    let mut upward = true;
+    let mut right = width - 1;
+    loop {
+        for i in 0..width {
+            let y = if upward { width - 1 - i } else { i };
+            for x in [right, right - 1] {
+                if !functional[y * width + x] {
+                    bits.push(grid[y * width + x] ^ (mask[y * width + x] == Color::Dark));
+                }
+            }
+        }
+        upward = !upward;
+        match right {
+            1 => break,
+            8 => right = 5,
+            _ => right -= 2,
+        }
+    }
+    let codewords: Vec<u8> = bits.chunks_exact(8).map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | bit as u8)).collect();
+
+    let data = deinterleave(&codewords, version, ec_level)?;
+    read_segments(&data, version)
+}
+
+/// Restores the order of the data codewords, correcting every block with its error correction
+/// codewords
+fn deinterleave(codewords: &[u8], version: Version, ec_level: EcLevel) -> Result<Vec<u8>, String> {
+    let invalid = |_| "Invalid QR code".to_string();
+    let len = qrcode::bits::Bits::new(version).max_len(ec_level).map_err(invalid)? / 8;
+
+    // Interleaving the indices of the codewords shows where each one goes
+    let low: Vec<u8> = (0..len).map(|i| i as u8).collect();
+    let high: Vec<u8> = (0..len).map(|i| (i >> 8) as u8).collect();
+    let (low, _) = qrcode::ec::construct_codewords(&low, version, ec_level).map_err(invalid)?;
+    let (high, _) = qrcode::ec::construct_codewords(&high, version, ec_level).map_err(invalid)?;
+    let order: Vec<usize> = low.iter().zip(&high).map(|(&l, &h)| l as usize | (h as usize) << 8).collect();
+
+    let mut data = vec![0; len];
+    for (&i, &codeword) in order.iter().zip(codewords) {
+        data[i] = codeword;
+    }
+
+    // The first codewords are the first of every block
+    let blocks = order.iter().position(|&i| i == 1).unwrap_or(1);
+    let mut starts = order[..blocks].to_vec();
+    starts.push(len);
+    let ec = &codewords[len..];
+    let ec_len = ec.len() / blocks;
+    let field = Field::new();
+    for (k, range) in starts.windows(2).enumerate() {
+        let mut block = data[range[0]..range[1]].to_vec();
+        block.extend((0..ec_len).map(|i| ec[i * blocks + k]));
+        field.correct(&mut block, ec_len).ok_or("The QR code is damaged beyond repair")?;
+        data[range[0]..range[1]].copy_from_slice(&block[..range[1] - range[0]]);
+    }
+
+    Ok(data)
+}
+
+/// GF(2^8) as QR codes use it, reducing by `x^8 + x^4 + x^3 + x^2 + 1`
+struct Field {
+    exp: [u8; 510],
+    log: [u8; 256],
+}
+
+impl Field {
+    fn new() -> Field {
+        let (mut exp, mut log) = ([0; 510], [0; 256]);
+        let mut x = 1u16;
+        for i in 0..255 {
+            (exp[i], exp[i + 255]) = (x as u8, x as u8);
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        Field { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 { 0 } else { self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize] }
+    }
+
+    fn inv(&self, a: u8) -> u8 {
+        self.exp[255 - self.log[a as usize] as usize]
+    }
+
+    /// The value at `x` of the polynomial with `coefficients`, lowest degree first
+    fn eval(&self, coefficients: &[u8], x: u8) -> u8 {
+        coefficients.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    /// Corrects `block`, data and then `ec_len` error correction codewords, highest degree first;
+    /// `None` when it has more errors than the code corrects
+    fn correct(&self, block: &mut [u8], ec_len: usize) -> Option<()> {
+        let n = block.len();
+        let syndromes: Vec<u8> = (0..ec_len)
+            .map(|j| block.iter().fold(0, |acc, &c| self.mul(acc, self.exp[j]) ^ c))
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(());
+        }
+
+        // Berlekamp-Massey for the error locator, lowest degree first
+        let (mut locator, mut previous) = (vec![1u8], vec![1u8]);
+        let (mut errors, mut shift, mut last) = (0, 1, 1u8);
+        for i in 0..ec_len {
+            let discrepancy = (1..=errors).fold(syndromes[i], |acc, j| acc ^ self.mul(*locator.get(j).unwrap_or(&0), syndromes[i - j]));
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let factor = self.mul(discrepancy, self.inv(last));
+            let before = locator.clone();
+            locator.resize(locator.len().max(previous.len() + shift), 0);
+            for (j, &p) in previous.iter().enumerate() {
+                locator[j + shift] ^= self.mul(factor, p);
+            }
+            if 2 * errors <= i {
+                (errors, previous, last, shift) = (i + 1 - errors, before, discrepancy, 1);
+            } else {
+                shift += 1;
+            }
+        }
+        while locator.last() == Some(&0) {
+            locator.pop();
+        }
+        if locator.len() - 1 != errors || 2 * errors > ec_len {
+            return None;
+        }
+
+        // Chien search: codeword i carries the power n - 1 - i, and errors sit at the inverse roots
+        let positions: Vec<usize> = (0..n).filter(|&i| self.eval(&locator, self.inv(self.exp[(n - 1 - i) % 255])) == 0).collect();
+        if positions.len() != errors {
+            return None;
+        }
+
+        // Forney, for a generator with the roots 1 to x^(ec_len - 1)
+        let evaluator: Vec<u8> = (0..ec_len)
+            .map(|k| (0..=k.min(locator.len() - 1)).fold(0, |acc, i| acc ^ self.mul(locator[i], syndromes[k - i])))
+            .collect();
+        let derivative: Vec<u8> = locator.iter().enumerate().skip(1).map(|(i, &c)| if i % 2 == 1 { c } else { 0 }).collect();
+        for i in positions {
+            let x = self.exp[(n - 1 - i) % 255];
+            let x_inv = self.inv(x);
+            let denominator = self.eval(&derivative, x_inv);
+            if denominator == 0 {
+                return None;
+            }
+            block[i] ^= self.mul(x, self.mul(self.eval(&evaluator, x_inv), self.inv(denominator)));
+        }
+
+        let corrected = (0..ec_len).all(|j| block.iter().fold(0, |acc, &c| self.mul(acc, self.exp[j]) ^ c) == 0);
+        corrected.then_some(())
+    }
+}
+
+/// Reads the numeric, alphanumeric and byte segments of the data codewords as text
+fn read_segments(data: &[u8], version: Version) -> Result<String, String> {
+    let mut bits = data.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1));
+    let mut read = |n: usize| -> Result<usize, String> {
+        (0..n).try_fold(0, |acc, _| Ok(acc << 1 | bits.next().ok_or("The QR code is truncated")? as usize))
+    };
+
+    let mut text = Vec::new();
+    loop {
+        let mode = match read(4) {
+            Ok(0) | Err(_) => break,
+            Ok(1) => Mode::Numeric,
+            Ok(2) => Mode::Alphanumeric,
+            Ok(4) => Mode::Byte,
+            Ok(mode) => return Err(format!("Unsupported QR code mode {mode}")),
+        };
+        let count = read(mode.length_bits_count(version))?;
+
+        match mode {
+            Mode::Numeric => {
+                for digits in (0..count).step_by(3).map(|i| (count - i).min(3)) {
+                    let value = read([0, 4, 7, 10][digits])?;
+                    text.extend(format!("{value:0digits$}").bytes());
+                }
+            }
+            Mode::Alphanumeric => {
+                for chars in (0..count).step_by(2).map(|i| (count - i).min(2)) {
+                    let value = read([0, 6, 11][chars])?;
+                    let pair = [value / 45, value % 45];
+                    for &v in &pair[2 - chars..] {
+                        text.push(*ALPHANUMERIC.get(v).ok_or("Invalid QR code")?);
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..count {
+                    text.push(read(8)? as u8);
+                }
+            }
+        }
+    }
+
+    String::from_utf8(text).map_err(|_| "The QR code does not hold text".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(text: &str) -> (Vec<bool>, usize) {
+        let code = super::super::code(text).unwrap();
+        (code.to_colors().iter().map(|&c| c == Color::Dark).collect(), code.width())
+    }
+
+    #[test]
+    fn reed_solomon() {
+        let data = b"ssss-rs".to_vec();
+        let block: Vec<u8> = [data.clone(), qrcode::ec::create_error_correction_code(&data, 10)].concat();
+        let field = Field::new();
+
+        for errors in 0..=5 {
+            let mut damaged = block.clone();
+            for i in 0..errors {
+                damaged[i * 3] ^= 0x5a + i as u8;
+            }
+            assert_eq!(Some(()), field.correct(&mut damaged, 10));
+            assert_eq!(block, damaged);
+        }
+
+        let mut damaged = block.clone();
+        for byte in &mut damaged[..8] {
+            *byte ^= 0xff;
+        }
+        assert!(field.correct(&mut damaged, 10).is_none() || damaged != block);
+    }
+
+    #[test]
+    fn damaged_codes() {
+        let text = "v2-00c0ffee-01-3-5-2-QUJDQQ==";
+        let (mut modules, width) = grid(text);
+        assert_eq!(Ok(text.to_string()), decode_modules(&modules, width));
+
+        // A corner of data modules is within the medium level of error correction
+        for y in width - 4..width {
+            for x in width - 4..width {
+                modules[y * width + x] = !modules[y * width + x];
+            }
+        }
+        assert_eq!(Ok(text.to_string()), decode_modules(&modules, width));
+
+        for i in (width * 9..width * width).step_by(3) {
+            modules[i] = !modules[i];
+        }
+        assert!(decode_modules(&modules, width).is_err());
+    }
+}
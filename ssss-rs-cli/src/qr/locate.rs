@@ -0,0 +1,358 @@
+//! Finding a QR symbol in a greyscale image and sampling its modules
+//!
+//! The image is binarised by comparing the median of each 3×3 neighbourhood, which ignores specks
+//! of dust, with the mean of a wide one, which follows uneven lighting.
+//! The three finder patterns show as dark, light, dark, light and dark runs in the ratio
+//! 1:1:3:1:1 along any line through their centres, whatever the rotation. They give the size of
+//! the symbol; the alignment pattern in the lower right, where there is one, adds the fourth point
+//! of a perspective transform from modules to pixels.
+
+/// Dark pixels of a greyscale image
+pub(super) struct Bitmap {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Binarises `luma`, a byte per pixel in rows of `width`
+    pub(super) fn new(luma: &[u8], width: usize, height: usize) -> Option<Bitmap> {
+        let (min, max) = luma.iter().fold((0xff, 0), |(min, max), &l| (l.min(min), l.max(max)));
+        if max - min < 0x20 {
+            return None;
+        }
+
+        // Sums of the rectangles from the origin, for means over any rectangle
+        let stride = width + 1;
+        let mut sums = vec![0u64; stride * (height + 1)];
+        for y in 0..height {
+            let mut row = 0;
+            for x in 0..width {
+                row += luma[y * width + x] as u64;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+            }
+        }
+        let mean = |x: usize, y: usize, radius: usize| {
+            let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+            let (right, bottom) = ((x + radius + 1).min(width), (y + radius + 1).min(height));
+            let sum = sums[bottom * stride + right] + sums[top * stride + left] - sums[top * stride + right] - sums[bottom * stride + left];
+            sum as f64 / ((right - left) * (bottom - top)) as f64
+        };
+
+        // The neighbourhood spans several modules of any symbol filling a good part of the image
+        let radius = (width.max(height) / 8).max(8);
+        let margin = (max - min) as f64 / 8.0;
+        // The median of the pixel and its neighbours ignores a lone speck
+        let median = |x: usize, y: usize| {
+            let mut near = [0u8; 9];
+            let mut n = 0;
+            for y in y.saturating_sub(1)..(y + 2).min(height) {
+                for x in x.saturating_sub(1)..(x + 2).min(width) {
+                    near[n] = luma[y * width + x];
+                    n += 1;
+                }
+            }
+            near[..n].sort_unstable();
+            near[n / 2] as f64
+        };
+        let dark = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                median(x, y) < mean(x, y, radius) - margin
+            })
+            .collect();
+        Some(Bitmap { width, height, dark })
+    }
+
+    fn is_dark(&self, x: f64, y: f64) -> Option<bool> {
+        let (x, y) = (x.round(), y.round());
+        (x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height)
+            .then(|| self.dark[y as usize * self.width + x as usize])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+impl Point {
+    fn distance(self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// A finder pattern candidate: its centre, the length of the pattern along a row and how often it
+/// was found
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    center: Point,
+    span: f64,
+    count: usize,
+}
+
+/// Module size of a 1:1:3:1:1 run, if the runs are in about that ratio
+fn finder_module(runs: &[usize; 5]) -> Option<f64> {
+    let total: usize = runs.iter().sum();
+    let module = total as f64 / 7.0;
+    let fits = |run: usize, modules: f64| (run as f64 - modules * module).abs() < modules.max(1.2) * module * 0.6;
+    (total >= 7 && runs.iter().zip([1.0, 1.0, 3.0, 1.0, 1.0]).all(|(&run, modules)| fits(run, modules))).then_some(module)
+}
+
+/// The five runs of a finder pattern on the line through `(x, y)` in direction `(dx, dy)`, with
+/// the offset of their middle from `(x, y)` in steps
+fn runs_through(bitmap: &Bitmap, x: f64, y: f64, dx: f64, dy: f64, limit: usize) -> Option<([usize; 5], f64)> {
+    if bitmap.is_dark(x, y) != Some(true) {
+        return None;
+    }
+
+    // Dark, light and dark runs walking out of the centre module either way; where modules are
+    // a few pixels wide, a lone pixel of the other colour is a speckle
+    let speckles = limit >= 42;
+    let walk = |sign: f64| {
+        let at = |step: usize| bitmap.is_dark(x + sign * step as f64 * dx, y + sign * step as f64 * dy);
+        let mut runs = [0; 3];
+        let mut step = 0;
+        for (run, dark) in runs.iter_mut().zip([true, false, true]) {
+            while *run <= limit && (at(step) == Some(dark) || speckles && *run > 0 && at(step).is_some() && at(step + 1) == Some(dark)) {
+                *run += 1;
+                step += 1;
+            }
+            if *run == 0 || *run > limit {
+                return None;
+            }
+        }
+        Some(runs)
+    };
+    let (back, forth) = (walk(-1.0)?, walk(1.0)?);
+
+    let runs = [back[2], back[1], back[0] + forth[0] - 1, forth[1], forth[2]];
+    Some((runs, (forth[0] as f64 - back[0] as f64) / 2.0))
+}
+
+/// Finder patterns found by scanning every row, then checking across each hit vertically and
+/// diagonally
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut finders: Vec<Finder> = Vec::new();
+    for y in 0..bitmap.height {
+        let row = &bitmap.dark[y * bitmap.width..][..bitmap.width];
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (x, &dark) in row.iter().enumerate() {
+            match runs.last_mut() {
+                Some((start, len)) if row[*start] == dark => *len += 1,
+                _ => runs.push((x, 1)),
+            }
+        }
+
+        for window in runs.windows(5).filter(|window| row[window[0].0]) {
+            let lens = [window[0].1, window[1].1, window[2].1, window[3].1, window[4].1];
+            let Some(module) = finder_module(&lens) else { continue };
+            let limit = (module * 7.0 * 2.0) as usize;
+
+            let x = window[2].0 as f64 + window[2].1 as f64 / 2.0;
+            let Some((vertical, dy)) = runs_through(bitmap, x, y as f64, 0.0, 1.0, limit) else { continue };
+            if finder_module(&vertical).is_none() {
+                continue;
+            }
+            let y = y as f64 + dy;
+            let Some((horizontal, dx)) = runs_through(bitmap, x, y, 1.0, 0.0, limit) else { continue };
+            if finder_module(&horizontal).is_none() {
+                continue;
+            }
+            let x = x + dx;
+            let diagonal = runs_through(bitmap, x, y, 1.0, 1.0, limit);
+            if diagonal.is_none_or(|(runs, _)| finder_module(&runs).is_none()) {
+                continue;
+            }
+
+            let center = Point { x, y };
+            let span = horizontal.iter().sum::<usize>() as f64;
+            match finders.iter_mut().find(|f| f.center.distance(center) < f.span / 3.5 && (f.span - span).abs() < f.span / 2.0) {
+                Some(finder) => {
+                    let n = finder.count as f64;
+                    finder.center = Point { x: (finder.center.x * n + x) / (n + 1.0), y: (finder.center.y * n + y) / (n + 1.0) };
+                    finder.span = (finder.span * n + span) / (n + 1.0);
+                    finder.count += 1;
+                }
+                None => finders.push(Finder { center, span, count: 1 }),
+            }
+        }
+    }
+    finders
+}
+
+/// The upper left, upper right and lower left finder patterns: the three of a similar size that
+/// come closest to an isosceles right triangle
+fn pick_finders(mut finders: Vec<Finder>) -> Option<[Finder; 3]> {
+    finders.sort_by_key(|f| std::cmp::Reverse(f.count));
+    finders.truncate(12);
+
+    let mut best: Option<(f64, [Finder; 3])> = None;
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                let three = [finders[i], finders[j], finders[k]];
+                let spans = three.map(|f| f.span);
+                let (smallest, largest) = (spans.iter().cloned().fold(f64::MAX, f64::min), spans.iter().cloned().fold(0.0, f64::max));
+                if largest > smallest * 1.4 {
+                    continue;
+                }
+
+                // The corner is opposite the longest side
+                let sides = [(1, 2), (0, 2), (0, 1)].map(|(a, b)| three[a].center.distance(three[b].center));
+                let corner = (0..3).max_by(|&a, &b| sides[a].total_cmp(&sides[b])).expect("three sides");
+                let [mut right, mut down] = [(corner + 1) % 3, (corner + 2) % 3].map(|i| three[i]);
+                let corner = three[corner];
+                let (a, b) = (sub(right.center, corner.center), sub(down.center, corner.center));
+                let (la, lb) = (a.x.hypot(a.y), b.x.hypot(b.y));
+                if la < corner.span || lb < corner.span {
+                    continue;
+                }
+
+                let cos = (a.x * b.x + a.y * b.y) / (la * lb);
+                let score = cos.abs() + (la / lb).ln().abs();
+                if cos.abs() > 0.3 || score > 0.5 || best.is_some_and(|(s, _)| s <= score) {
+                    continue;
+                }
+                // Upper right is clockwise from lower left, as seen with y pointing down
+                if a.x * b.y - a.y * b.x < 0.0 {
+                    std::mem::swap(&mut right, &mut down);
+                }
+                best = Some((score, [corner, right, down]));
+            }
+        }
+    }
+    best.map(|(_, finders)| finders)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    Point { x: a.x - b.x, y: a.y - b.y }
+}
+
+/// A projective map from module coordinates to pixels
+struct Transform([f64; 8]);
+
+impl Transform {
+    /// The transform taking each of four `from` points to the `to` point at the same index
+    fn new(from: [Point; 4], to: [Point; 4]) -> Option<Transform> {
+        // Two equations per point in the eight unknowns, with the last entry fixed at one
+        let mut rows = [[0.0; 9]; 8];
+        for (i, (f, t)) in from.iter().zip(&to).enumerate() {
+            rows[2 * i] = [f.x, f.y, 1.0, 0.0, 0.0, 0.0, -f.x * t.x, -f.y * t.x, t.x];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, f.x, f.y, 1.0, -f.x * t.y, -f.y * t.y, t.y];
+        }
+
+        for column in 0..8 {
+            let pivot = (column..8).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+            if rows[pivot][column].abs() < 1e-9 {
+                return None;
+            }
+            rows.swap(column, pivot);
+            for row in 0..8 {
+                if row != column {
+                    let pivot = rows[column];
+                    let factor = rows[row][column] / pivot[column];
+                    for (value, p) in rows[row].iter_mut().zip(pivot).skip(column) {
+                        *value -= factor * p;
+                    }
+                }
+            }
+        }
+        Some(Transform(std::array::from_fn(|i| rows[i][8] / rows[i][i])))
+    }
+
+    fn apply(&self, x: f64, y: f64) -> Point {
+        let h = &self.0;
+        let w = h[6] * x + h[7] * y + 1.0;
+        Point { x: (h[0] * x + h[1] * y + h[2]) / w, y: (h[3] * x + h[4] * y + h[5]) / w }
+    }
+}
+
+/// The centre of the alignment pattern closest to `estimate`, a dark module in a light ring in a
+/// dark ring, one module being `right` and `down`
+fn find_alignment(bitmap: &Bitmap, estimate: Point, right: Point, down: Point) -> Option<Point> {
+    let module = right.x.hypot(right.y);
+    let reach = (module * 4.0).ceil() as i64;
+
+    // The pattern matches at a few neighbouring pixels; their mean is its centre
+    let (mut best, mut sum, mut count) = (0, Point { x: 0.0, y: 0.0 }, 0.0);
+    for dy in -reach..=reach {
+        for dx in -reach..=reach {
+            let center = Point { x: estimate.x + dx as f64, y: estimate.y + dy as f64 };
+            let score = (-2..=2i32)
+                .flat_map(|j| (-2..=2i32).map(move |i| (i, j)))
+                .filter(|&(i, j)| {
+                    let ring = i.abs().max(j.abs());
+                    let x = center.x + i as f64 * right.x + j as f64 * down.x;
+                    let y = center.y + i as f64 * right.y + j as f64 * down.y;
+                    bitmap.is_dark(x, y) == Some(ring != 1)
+                })
+                .count();
+            if score > best {
+                (best, sum, count) = (score, Point { x: 0.0, y: 0.0 }, 0.0);
+            }
+            if score == best {
+                (sum.x, sum.y, count) = (sum.x + center.x, sum.y + center.y, count + 1.0);
+            }
+        }
+    }
+    (best >= 22).then(|| Point { x: sum.x / count, y: sum.y / count })
+}
+
+/// Candidate module grids of the symbol in the image, as rows of `width` modules, dark being
+/// `true`; the most likely size first
+pub(super) fn grids(bitmap: &Bitmap) -> Vec<(Vec<bool>, usize)> {
+    let Some([corner, right, down]) = pick_finders(find_finders(bitmap)) else {
+        return vec![];
+    };
+
+    // Along a row, a finder pattern spans seven modules over the cosine of the rotation
+    let angle = (right.center.y - corner.center.y).atan2(right.center.x - corner.center.x);
+    let folded = (angle + std::f64::consts::FRAC_PI_4).rem_euclid(std::f64::consts::FRAC_PI_2) - std::f64::consts::FRAC_PI_4;
+    let module = (corner.span + right.span + down.span) / 3.0 / 7.0 * folded.cos();
+    let sides = (corner.center.distance(right.center) + corner.center.distance(down.center)) / 2.0;
+    let version = ((sides / module + 7.0 - 17.0) / 4.0).round() as i64;
+
+    let mut grids = Vec::new();
+    for version in [version, version - 1, version + 1].into_iter().filter(|v| (1..=40).contains(v)) {
+        let width = 17 + 4 * version as usize;
+        let far = width as f64 - 3.5;
+        let from = [Point { x: 3.5, y: 3.5 }, Point { x: far, y: 3.5 }, Point { x: 3.5, y: far }];
+        let to = [corner.center, right.center, down.center];
+
+        // Without an alignment pattern, the lower right finder would complete a parallelogram
+        let step = |p: Point| Point { x: p.x / (width as f64 - 7.0), y: p.y / (width as f64 - 7.0) };
+        let (across, along) = (step(sub(right.center, corner.center)), step(sub(down.center, corner.center)));
+        let parallelogram = Point {
+            x: right.center.x + down.center.x - corner.center.x,
+            y: right.center.y + down.center.y - corner.center.y,
+        };
+        let (fourth_from, fourth_to) = match version {
+            1 => (Point { x: far, y: far }, parallelogram),
+            _ => {
+                let estimate = Point { x: parallelogram.x - 3.0 * (across.x + along.x), y: parallelogram.y - 3.0 * (across.y + along.y) };
+                let found = find_alignment(bitmap, estimate, across, along).unwrap_or(estimate);
+                (Point { x: far - 3.0, y: far - 3.0 }, found)
+            }
+        };
+        let Some(transform) = Transform::new([from[0], from[1], from[2], fourth_from], [to[0], to[1], to[2], fourth_to]) else {
+            continue;
+        };
+
+        // Vote over a few pixels around the middle of each module, against speckles
+        let spread = if module >= 4.0 { 1.0 } else { 0.0 };
+        let grid = (0..width * width)
+            .map(|i| {
+                let center = transform.apply((i % width) as f64 + 0.5, (i / width) as f64 + 0.5);
+                let votes = [(0.0, 0.0), (-spread, 0.0), (spread, 0.0), (0.0, -spread), (0.0, spread)]
+                    .iter()
+                    .filter(|(dx, dy)| bitmap.is_dark(center.x + dx, center.y + dy) == Some(true))
+                    .count();
+                votes >= 3
+            })
+            .collect();
+        grids.push((grid, width));
+    }
+    grids
+}
@@ -0,0 +1,241 @@
+//! QR codes of shards for paper backups: rendering to the terminal, SVG and PNG, and reading back
+//! PNG images, such as scans or photos of a printed card
+
+mod decode;
+mod locate;
+
+use qrcode::types::{Color, EcLevel};
+use qrcode::QrCode;
+
+/// Pixels per module in PNG images
+const PNG_SCALE: usize = 8;
+
+/// Light modules around the symbol, as the standard asks for
+const QUIET_ZONE: usize = 4;
+
+/// A QR code holding `text`, with medium error correction
+pub(crate) fn code(text: &str) -> Result<QrCode, String> {
+    QrCode::with_error_correction_level(text, EcLevel::M).map_err(|x| format!("Could not create QR code: {x}"))
+}
+
+fn is_dark(code: &QrCode, x: usize, y: usize) -> bool {
+    code[(x, y)] == Color::Dark
+}
+
+/// Draws `code` with ANSI colours and half blocks, two rows of modules per line, followed by the
+/// caption
+pub(crate) fn ansi(code: &QrCode, caption: &[String]) -> String {
+    let width = code.width() + 2 * QUIET_ZONE;
+    let dark = |x: usize, y: usize| {
+        (QUIET_ZONE..QUIET_ZONE + code.width()).contains(&x)
+            && (QUIET_ZONE..QUIET_ZONE + code.width()).contains(&y)
+            && is_dark(code, x - QUIET_ZONE, y - QUIET_ZONE)
+    };
+
+    let mut output = String::new();
+    for y in (0..width).step_by(2) {
+        for x in 0..width {
+            // The foreground colours the upper half, the background the lower
+            let fg = if dark(x, y) { 30 } else { 97 };
+            let bg = if dark(x, y + 1) { 40 } else { 107 };
+            output.push_str(&format!("\x1b[{fg};{bg}m\u{2580}"));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    for line in caption {
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// An SVG image of `code` with the caption below, one module per user unit
+pub(crate) fn svg(code: &QrCode, caption: &[String]) -> String {
+    const FONT_SIZE: f64 = 1.5;
+    let symbol = code.width() + 2 * QUIET_ZONE;
+    // Monospace glyphs are about 0.6 em wide
+    let longest = caption.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let width = symbol.max((longest as f64 * 0.6 * FONT_SIZE).ceil() as usize + 2);
+    let height = symbol as f64 + caption.len() as f64 * FONT_SIZE * 1.3 + 1.0;
+
+    let mut path = String::new();
+    for y in 0..code.width() {
+        for x in (0..code.width()).filter(|&x| is_dark(code, x, y)) {
+            path.push_str(&format!("M{},{}h1v1h-1z", x + (width - code.width()) / 2, y + QUIET_ZONE));
+        }
+    }
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\" width=\"{}mm\" height=\"{}mm\">\n",
+        width * 2,
+        height * 2.0
+    );
+    output.push_str(&format!("<rect width=\"{width}\" height=\"{height}\" fill=\"#fff\"/>\n"));
+    output.push_str(&format!("<path d=\"{path}\" fill=\"#000\" shape-rendering=\"crispEdges\"/>\n"));
+    for (i, line) in caption.iter().enumerate() {
+        output.push_str(&format!(
+            "<text x=\"{}\" y=\"{:.2}\" font-family=\"monospace\" font-size=\"{FONT_SIZE}\" text-anchor=\"middle\">{}</text>\n",
+            width as f64 / 2.0,
+            symbol as f64 + (i + 1) as f64 * FONT_SIZE * 1.3,
+            escape_xml(line)
+        ));
+    }
+    output.push_str("</svg>\n");
+    output
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// A greyscale PNG image of `code`; the first caption line becomes its title, the others a comment
+pub(crate) fn png(code: &QrCode, caption: &[String]) -> Result<Vec<u8>, String> {
+    let side = (code.width() + 2 * QUIET_ZONE) * PNG_SCALE;
+    let mut pixels = vec![0xffu8; side * side];
+    for y in 0..code.width() {
+        for x in (0..code.width()).filter(|&x| is_dark(code, x, y)) {
+            for row in (y + QUIET_ZONE) * PNG_SCALE..(y + QUIET_ZONE + 1) * PNG_SCALE {
+                let start = row * side + (x + QUIET_ZONE) * PNG_SCALE;
+                pixels[start..start + PNG_SCALE].fill(0);
+            }
+        }
+    }
+
+    let error = |x: png::EncodingError| format!("Could not create PNG image: {x}");
+    let mut output = Vec::new();
+    let mut encoder = png::Encoder::new(&mut output, side as u32, side as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    if let Some((title, comment)) = caption.split_first() {
+        encoder.add_text_chunk("Title".into(), title.clone()).map_err(error)?;
+        encoder.add_text_chunk("Comment".into(), comment.join("\n")).map_err(error)?;
+    }
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(&pixels).map_err(error)?;
+    writer.finish().map_err(error)?;
+    Ok(output)
+}
+
+/// Reads the text held by the QR code in a PNG image
+pub(crate) fn read_png(bytes: &[u8]) -> Result<String, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|x| format!("Could not read PNG image: {x}"))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|x| format!("Could not read PNG image: {x}"))?;
+
+    let channels = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let luma: Vec<u8> = (0..width * height)
+        .map(|i| {
+            let pixel = &buffer[i / width * info.line_size + i % width * channels..][..channels];
+            match info.color_type {
+                png::ColorType::GrayscaleAlpha if pixel[1] < 0x80 => 0xff,
+                png::ColorType::Rgba if pixel[3] < 0x80 => 0xff,
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => pixel[0],
+                _ => ((pixel[0] as u32 * 3 + pixel[1] as u32 * 6 + pixel[2] as u32) / 10) as u8,
+            }
+        })
+        .collect();
+
+    let no_code = || "No QR code found in the image".to_string();
+    let bitmap = locate::Bitmap::new(&luma, width, height).ok_or_else(no_code)?;
+
+    // The size of the symbol is estimated, so the neighbouring sizes get a try as well
+    let mut result = Err(no_code());
+    for (grid, modules) in locate::grids(&bitmap) {
+        result = decode::decode_modules(&grid, modules);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trip() {
+        // Mixed segments, several blocks and version information
+        let long = format!("v2-00c0ffee-01-3-5-2-{}", "QUJD".repeat(60));
+        for text in ["v2-00c0ffee-01-3-5-2-QUJDQQ==", "12345678901234567890", "SSSS1QPZRY9X8GF2", long.as_str()] {
+            let image = png(&code(text).unwrap(), &[text.to_string()]).unwrap();
+
+            assert_eq!(text, read_png(&image).unwrap());
+        }
+    }
+
+    /// A greyscale PNG of `code` as a scanner might see it: `scale` pixels per module, turned by
+    /// `degrees`, unevenly lit and speckled with noise
+    fn scan(code: &QrCode, scale: f64, degrees: f64) -> Vec<u8> {
+        let modules = (code.width() + 2 * QUIET_ZONE) as f64;
+        let side = (modules * scale * 1.5) as usize;
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut noise = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        let mut pixels = vec![0u8; side * side];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = ((i % side) as f64 - side as f64 / 2.0, (i / side) as f64 - side as f64 / 2.0);
+            let (u, v) = ((x * cos + y * sin) / scale + modules / 2.0, (y * cos - x * sin) / scale + modules / 2.0);
+            let (u, v) = (u - QUIET_ZONE as f64, v - QUIET_ZONE as f64);
+            let inside = (0.0..code.width() as f64).contains(&u) && (0.0..code.width() as f64).contains(&v);
+            let dark = inside && is_dark(code, u as usize, v as usize);
+
+            let light = 200.0 + 40.0 * (i % side) as f64 / side as f64;
+            let value = if dark { 50.0 } else { light } + (noise() % 61) as f64 - 30.0;
+            *pixel = match noise() % 100 {
+                0 => 0,
+                1 => 0xff,
+                _ => value.clamp(0.0, 255.0) as u8,
+            };
+        }
+
+        let mut image = Vec::new();
+        let mut encoder = png::Encoder::new(&mut image, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+        writer.finish().unwrap();
+        image
+    }
+
+    #[test]
+    fn scans_are_read() {
+        let long = format!("v2-00c0ffee-01-3-5-2-{}", "QUJD".repeat(30));
+        for text in ["v2-00c0ffee-01-3-5-2-QUJDQQ==", "12345678901234567890", long.as_str()] {
+            let code = code(text).unwrap();
+            for (scale, degrees) in [(3.5, 0.0), (5.3, 17.0), (4.2, -128.0), (6.0, 90.0), (4.6, 45.0)] {
+                assert_eq!(Ok(text.to_string()), read_png(&scan(&code, scale, degrees)), "{scale} px at {degrees} degrees");
+            }
+        }
+    }
+
+    #[test]
+    fn renderings_hold_the_caption() {
+        let code = code("01-QQ==").unwrap();
+        let caption = ["01-QQ==".to_string(), "shard 1 <of> 2".to_string()];
+
+        assert!(ansi(&code, &caption).ends_with("01-QQ==\nshard 1 <of> 2\n"));
+        assert!(svg(&code, &caption).contains(">shard 1 &lt;of&gt; 2</text>"));
+    }
+
+    #[test]
+    fn blank_image() {
+        let mut image = Vec::new();
+        let mut encoder = png::Encoder::new(&mut image, 16, 16);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xff; 256]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(Err("No QR code found in the image".into()), read_png(&image));
+    }
+}
@@ -0,0 +1,144 @@
+//! Splitting and combining throughput, against the byte-at-a-time implementation this crate had
+//! before its arithmetic worked on whole buffers
+//!
+//! Run with `cargo bench -p ssss-rs-core`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::Rng;
+use ssss_rs_core::{decode, encode, ShamirScheme};
+
+const SIZES: [usize; 3] = [32, 4 * 1024, 256 * 1024];
+const THRESHOLD: u8 = 3;
+const NUM_SHARDS: u8 = 5;
+
+/// The previous implementation: an element per `u64`, branching multiplication, an inverse table
+/// found by brute force, and polynomials evaluated by recomputing every power of x
+mod baseline {
+    use rand::RngExt;
+    use std::sync::OnceLock;
+
+    const K: usize = 8;
+    const Q: u64 = 283;
+
+    #[derive(Copy, Clone, PartialEq)]
+    struct Gf(u64);
+
+    impl Gf {
+        fn mul(self, rhs: Gf) -> Gf {
+            let (mut a, b, mut c) = (self.0, rhs.0, 0);
+            for i in 0..K {
+                if (b >> i) & 1 == 1 {
+                    c ^= a;
+                }
+                a <<= 1;
+                if (a >> K) & 1 == 1 {
+                    a ^= Q;
+                }
+            }
+            Gf(c)
+        }
+
+        fn invert(self) -> Gf {
+            static LUT: OnceLock<Vec<Gf>> = OnceLock::new();
+            LUT.get_or_init(|| {
+                let mut lut = vec![Gf(0); 256];
+                for x in 1..256 {
+                    lut[x as usize] = (1..256).map(Gf).find(|&y| Gf(x).mul(y) == Gf(1)).unwrap();
+                }
+                lut
+            })[self.0 as usize]
+        }
+    }
+
+    fn apply_x(poly: &[Gf], x: u8) -> Gf {
+        let mut val = 0;
+        for (i, &c) in poly.iter().enumerate() {
+            let mut term = c;
+            for _ in 0..i {
+                term = term.mul(Gf(x as u64));
+            }
+            val ^= term.0;
+        }
+        Gf(val)
+    }
+
+    pub fn split(threshold: u8, num_shards: u8, secret: &[u8]) -> Vec<Vec<u8>> {
+        let mut rng = rand::rng();
+        let mut shares = vec![Vec::with_capacity(secret.len()); num_shards as usize];
+        for &byte in secret {
+            let mut poly = vec![Gf(byte as u64)];
+            for _ in 1..threshold {
+                poly.push(Gf(rng.random::<u8>() as u64));
+            }
+            for x in 1..=num_shards {
+                shares[x as usize - 1].push(apply_x(&poly, x).0 as u8);
+            }
+        }
+        shares
+    }
+
+    pub fn combine(x: &[u8], shares: &[Vec<u8>]) -> Vec<u8> {
+        (0..shares[0].len())
+            .map(|i| {
+                let mut sum = 0;
+                for j in 0..x.len() {
+                    let mut mult = Gf(shares[j][i] as u64);
+                    for m in 0..x.len() {
+                        if j == m { continue; }
+                        mult = mult.mul(Gf(x[m] as u64)).mul(Gf((x[m] ^ x[j]) as u64).invert());
+                    }
+                    sum ^= mult.0;
+                }
+                sum as u8
+            })
+            .collect()
+    }
+}
+
+fn secret(len: usize) -> Vec<u8> {
+    let mut secret = vec![0u8; len];
+    rand::rng().fill_bytes(&mut secret);
+    secret
+}
+
+fn split(c: &mut Criterion) {
+    let scheme = ShamirScheme::new(THRESHOLD.into(), NUM_SHARDS.into());
+    let mut group = c.benchmark_group("split");
+
+    for len in SIZES {
+        let secret = secret(len);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("baseline", len), &secret, |b, secret| {
+            b.iter(|| baseline::split(THRESHOLD, NUM_SHARDS, black_box(secret)))
+        });
+        group.bench_with_input(BenchmarkId::new("slices", len), &secret, |b, secret| {
+            b.iter(|| encode(&scheme, black_box(secret)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn combine(c: &mut Criterion) {
+    let scheme = ShamirScheme::new(THRESHOLD.into(), NUM_SHARDS.into());
+    let mut group = c.benchmark_group("combine");
+
+    for len in SIZES {
+        let secret = secret(len);
+        let shards = encode(&scheme, &secret).unwrap();
+        let shards = &shards[..THRESHOLD as usize];
+        let shares = &baseline::split(THRESHOLD, NUM_SHARDS, &secret)[..THRESHOLD as usize];
+        let x: Vec<u8> = (1..=THRESHOLD).collect();
+
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("baseline", len), shares, |b, shares| {
+            b.iter(|| baseline::combine(&x, black_box(shares)))
+        });
+        group.bench_with_input(BenchmarkId::new("slices", len), shards, |b, shards| {
+            b.iter(|| decode(black_box(shards)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, split, combine);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+//! Shares in the formats of other secret sharing tools, so their backups can be restored and
+//! regenerated with ssss-rs
+
+pub mod slip39;
+pub mod ssss;
+pub mod vault;
@@ -0,0 +1,110 @@
+//! The four-round Feistel cipher that SLIP-39 applies to the master secret, keyed by the passphrase
+
+use sha2::{Digest, Sha256};
+
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+
+/// SHA-256 block size, in bytes
+const BLOCK_LEN: usize = 64;
+
+pub(super) fn encrypt(master_secret: &[u8], passphrase: &[u8], exponent: u8, identifier: u16, extendable: bool) -> Vec<u8> {
+    feistel(master_secret, passphrase, exponent, &salt(identifier, extendable), 0..ROUND_COUNT)
+}
+
+pub(super) fn decrypt(encrypted: &[u8], passphrase: &[u8], exponent: u8, identifier: u16, extendable: bool) -> Vec<u8> {
+    feistel(encrypted, passphrase, exponent, &salt(identifier, extendable), (0..ROUND_COUNT).rev())
+}
+
+/// Extendable backups leave out the identifier, so new shares may be issued under another one
+fn salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        vec![]
+    } else {
+        [b"shamir".as_slice(), &identifier.to_be_bytes()].concat()
+    }
+}
+
+fn feistel(data: &[u8], passphrase: &[u8], exponent: u8, salt: &[u8], rounds: impl Iterator<Item = u8>) -> Vec<u8> {
+    let (left, right) = data.split_at(data.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    let iterations = (BASE_ITERATION_COUNT << exponent) / ROUND_COUNT as u32;
+
+    for round in rounds {
+        let password = [&[round], passphrase].concat();
+        let f = pbkdf2_sha256(&password, &[salt, &right].concat(), iterations, right.len());
+        let next: Vec<u8> = left.iter().zip(f).map(|(l, f)| l ^ f).collect();
+        left = std::mem::replace(&mut right, next);
+    }
+
+    [right, left].concat()
+}
+
+pub(super) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_key: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    let outer_key: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+
+    let inner = Sha256::new().chain_update(inner_key).chain_update(message).finalize();
+    Sha256::new().chain_update(outer_key).chain_update(inner).finalize().into()
+}
+
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(len);
+
+    for block in 1..=len.div_ceil(32) as u32 {
+        let mut u = hmac_sha256(password, &[salt, &block.to_be_bytes()].concat());
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(password, &u);
+            t.iter_mut().zip(u).for_each(|(t, u)| *t ^= u);
+        }
+        output.extend_from_slice(&t);
+    }
+
+    output.truncate(len);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn hmac_rfc4231_case_two() {
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?"))
+        );
+    }
+
+    #[test]
+    fn pbkdf2_rfc7914_vector() {
+        assert_eq!(
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783",
+            hex(&pbkdf2_sha256(b"passwd", b"salt", 1, 64))
+        );
+    }
+
+    #[test]
+    fn feistel_round_trip() {
+        let secret = b"0123456789abcdef";
+
+        for extendable in [true, false] {
+            let encrypted = encrypt(secret, b"TREZOR", 0, 7, extendable);
+            assert_ne!(secret.to_vec(), encrypted);
+            assert_eq!(secret.to_vec(), decrypt(&encrypted, b"TREZOR", 0, 7, extendable));
+            assert_ne!(secret.to_vec(), decrypt(&encrypted, b"", 0, 7, extendable));
+        }
+    }
+}
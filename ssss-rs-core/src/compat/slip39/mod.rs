@@ -0,0 +1,571 @@
+//! SLIP-0039 mnemonic shares, as imported by hardware wallets
+//!
+//! The master secret is encrypted with a passphrase, then shared in two levels: the encrypted
+//! secret is split among groups, and each group's share is split among its members. Shares are
+//! sequences of words from a list of 1024, ending in an RS1024 checksum. The sharing works in the
+//! same GF(2^8) as [`ShamirScheme`](crate::ShamirScheme), but hides a digest of the secret at
+//! x = 254 and the secret itself at x = 255, handing out the shares from x = 0.
+
+mod cipher;
+
+use crate::encoding::lookup;
+use crate::err::{Result, SsssErr};
+use crate::math::{GfPoly, GF};
+use rand::{Rng, RngExt};
+use std::{fmt, str, sync::OnceLock};
+
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+/// Bits encoded by a single word
+const RADIX_BITS: usize = 10;
+
+/// Words for identifier, iteration exponent, group and member parameters
+const HEADER_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+
+/// Shortest master secret, in bytes
+const MIN_SECRET_LEN: usize = 16;
+
+/// Most groups, and most members per group
+const MAX_SHARE_COUNT: u8 = 16;
+
+const DIGEST_LEN: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+/// The 1024 words of SLIP-39, sorted and unique in their first four letters
+pub(crate) fn words() -> &'static [&'static str] {
+    WORDS.get_or_init(|| WORDLIST.lines().collect())
+}
+
+/// Options for a two-level SLIP-39 split
+pub struct Slip39Scheme {
+    group_threshold: u8,
+    /// Member threshold and member count of every group
+    groups: Vec<(u8, u8)>,
+    passphrase: String,
+    iteration_exponent: u8,
+    extendable: bool,
+}
+
+impl Slip39Scheme {
+    /// Any `group_threshold` of the `groups` restore the secret; every group is given as its member
+    /// threshold and member count
+    #[must_use]
+    pub fn new(group_threshold: u8, groups: &[(u8, u8)]) -> Self {
+        Slip39Scheme {
+            group_threshold,
+            groups: groups.to_vec(),
+            passphrase: String::new(),
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+
+    /// Encrypts the master secret with `passphrase`, which is then needed to restore it
+    #[must_use]
+    pub fn with_passphrase(mut self, passphrase: &str) -> Self {
+        self.passphrase = passphrase.to_owned();
+        self
+    }
+
+    /// Makes the passphrase key derivation `2^exponent` times as expensive; 1 by default
+    #[must_use]
+    pub fn with_iteration_exponent(mut self, exponent: u8) -> Self {
+        self.iteration_exponent = exponent;
+        self
+    }
+
+    /// Ties the encryption to the identifier of this split, as in SLIP-39 before extendable backups
+    #[must_use]
+    pub fn without_extendable_backup(mut self) -> Self {
+        self.extendable = false;
+        self
+    }
+
+    fn validate(&self, master_secret: &[u8]) -> Result<()> {
+        if master_secret.len() < MIN_SECRET_LEN || !master_secret.len().is_multiple_of(2) {
+            return Err(SsssErr::InvalidSecretLength(master_secret.len()));
+        }
+        validate_passphrase(&self.passphrase)?;
+
+        let group_count = self.groups.len();
+        if self.group_threshold == 0 || self.group_threshold as usize > group_count || group_count > MAX_SHARE_COUNT as usize {
+            return Err(SsssErr::InvalidThreshold { threshold: self.group_threshold.into(), num_shards: group_count as u32 });
+        }
+        for &(threshold, count) in &self.groups {
+            // A single member restoring a group would make its other members pointless
+            if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT || (threshold == 1 && count > 1) {
+                return Err(SsssErr::InvalidThreshold { threshold: threshold.into(), num_shards: count.into() });
+            }
+        }
+        if self.iteration_exponent > 15 {
+            return Err(SsssErr::MalformedShard("the iteration exponent is at most 15".into()));
+        }
+        Ok(())
+    }
+}
+
+/// One member's share, written as a mnemonic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slip39Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Vec<u8>,
+}
+
+impl Slip39Share {
+    pub fn identifier(&self) -> u16 { self.identifier }
+    pub fn group_index(&self) -> u8 { self.group_index }
+    pub fn group_threshold(&self) -> u8 { self.group_threshold }
+    pub fn group_count(&self) -> u8 { self.group_count }
+    pub fn member_index(&self) -> u8 { self.member_index }
+    pub fn member_threshold(&self) -> u8 { self.member_threshold }
+
+    /// Parameters that all shares of one split have in common
+    fn common(&self) -> (u16, bool, u8, u8, u8, usize) {
+        (self.identifier, self.extendable, self.iteration_exponent, self.group_threshold, self.group_count, self.value.len())
+    }
+
+    fn to_words(&self) -> Vec<u16> {
+        let id_exp = (self.identifier as u32) << 5 | (self.extendable as u32) << 4 | self.iteration_exponent as u32;
+        let params = (self.group_index as u32) << 16
+            | (self.group_threshold as u32 - 1) << 12
+            | (self.group_count as u32 - 1) << 8
+            | (self.member_index as u32) << 4
+            | (self.member_threshold as u32 - 1);
+
+        let mut data: Vec<u16> = [id_exp, params]
+            .iter()
+            .flat_map(|&v| [(v >> RADIX_BITS) as u16, (v & 0x3ff) as u16])
+            .collect();
+
+        // The value is left-padded with zero bits to a whole number of words
+        let padding = (RADIX_BITS - (self.value.len() * 8) % RADIX_BITS) % RADIX_BITS;
+        let bits: Vec<bool> = std::iter::repeat_n(false, padding)
+            .chain(self.value.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)))
+            .collect();
+        data.extend(bits.chunks(RADIX_BITS).map(|word| word.iter().fold(0u16, |acc, &bit| acc << 1 | bit as u16)));
+
+        let checksum = rs1024_checksum(customization(self.extendable), &data);
+        data.extend(checksum);
+        data
+    }
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable { CUSTOMIZATION_EXTENDABLE } else { CUSTOMIZATION }
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(SsssErr::InvalidPassphrase)
+    }
+}
+
+fn rs1024_polymod(values: impl Iterator<Item = u32>) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48, 0x21b1f890, 0x3f3f120,
+    ];
+
+    values.fold(1, |chk, v| {
+        let b = chk >> 20;
+        let chk = (chk & 0xfffff) << 10 ^ v;
+        GEN.iter().enumerate().filter(|(i, _)| (b >> i) & 1 == 1).fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+fn rs1024_checksum(customization: &[u8], data: &[u16]) -> [u16; CHECKSUM_WORDS] {
+    let values = customization.iter().map(|&c| c as u32).chain(data.iter().map(|&d| d as u32));
+    let polymod = rs1024_polymod(values.chain([0; CHECKSUM_WORDS])) ^ 1;
+    [0, 1, 2].map(|i| (polymod >> (RADIX_BITS * (2 - i))) as u16 & 0x3ff)
+}
+
+fn rs1024_verify(customization: &[u8], data: &[u16]) -> bool {
+    let values = customization.iter().map(|&c| c as u32).chain(data.iter().map(|&d| d as u32));
+    rs1024_polymod(values) == 1
+}
+
+/// Value at `x` of the polynomials through `points`, byte by byte
+fn interpolate(points: &[(u8, Vec<u8>)], x: u8) -> Result<Vec<u8>> {
+    if let Some((_, y)) = points.iter().find(|(xi, _)| *xi == x) {
+        return Ok(y.clone());
+    }
+
+    let xs: Vec<u8> = points.iter().map(|(xi, _)| *xi).collect();
+    let weights: Vec<GF> = GfPoly::lagrange_basis(&xs)?.iter().map(|l| l.apply_x(x)).collect();
+
+    Ok((0..points[0].1.len())
+        .map(|i| {
+            let sum = points.iter().zip(&weights).fold(GF::new(0), |acc, ((_, y), &w)| acc + GF::new(y[i]) * w);
+            sum.value() as u8
+        })
+        .collect())
+}
+
+/// Splits `secret` so that any `threshold` of the `count` values restore it, with a digest at
+/// [`DIGEST_INDEX`] to detect a wrong restore
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Result<Vec<Vec<u8>>> {
+    if threshold == 1 {
+        return Ok(vec![secret.to_vec(); count as usize]);
+    }
+
+    let mut rng = rand::rng();
+    let random_count = threshold - 2;
+    let mut shares: Vec<(u8, Vec<u8>)> = (0..random_count)
+        .map(|i| {
+            let mut share = vec![0; secret.len()];
+            rng.fill_bytes(&mut share);
+            (i, share)
+        })
+        .collect();
+
+    let mut random_part = vec![0; secret.len() - DIGEST_LEN];
+    rng.fill_bytes(&mut random_part);
+    let digest = &cipher::hmac_sha256(&random_part, secret)[..DIGEST_LEN];
+
+    let mut base = shares.clone();
+    base.push((DIGEST_INDEX, [digest, &random_part].concat()));
+    base.push((SECRET_INDEX, secret.to_vec()));
+
+    for i in random_count..count {
+        shares.push((i, interpolate(&base, i)?));
+    }
+    Ok(shares.into_iter().map(|(_, share)| share).collect())
+}
+
+fn recover_secret(threshold: u8, points: &[(u8, Vec<u8>)]) -> Result<Vec<u8>> {
+    if threshold == 1 {
+        return Ok(points[0].1.clone());
+    }
+
+    let secret = interpolate(points, SECRET_INDEX)?;
+    let digest_share = interpolate(points, DIGEST_INDEX)?;
+    let (digest, random_part) = digest_share.split_at(DIGEST_LEN);
+
+    if !crate::integrity::digests_match(digest, &cipher::hmac_sha256(random_part, &secret)[..DIGEST_LEN]) {
+        return Err(SsssErr::IntegrityCheckFailed);
+    }
+    Ok(secret)
+}
+
+/// Encrypts `master_secret` and shares it; the result holds the member shares of every group
+pub fn encode(options: &Slip39Scheme, master_secret: &[u8]) -> Result<Vec<Vec<Slip39Share>>> {
+    options.validate(master_secret)?;
+
+    let identifier = rand::rng().random::<u16>() & 0x7fff;
+    let encrypted = cipher::encrypt(
+        master_secret,
+        options.passphrase.as_bytes(),
+        options.iteration_exponent,
+        identifier,
+        options.extendable,
+    );
+
+    let group_count = options.groups.len() as u8;
+    let group_shares = split_secret(options.group_threshold, group_count, &encrypted)?;
+
+    options
+        .groups
+        .iter()
+        .zip(group_shares)
+        .enumerate()
+        .map(|(group_index, (&(member_threshold, member_count), group_share))| {
+            Ok(split_secret(member_threshold, member_count, &group_share)?
+                .into_iter()
+                .enumerate()
+                .map(|(member_index, value)| Slip39Share {
+                    identifier,
+                    extendable: options.extendable,
+                    iteration_exponent: options.iteration_exponent,
+                    group_index: group_index as u8,
+                    group_threshold: options.group_threshold,
+                    group_count,
+                    member_index: member_index as u8,
+                    member_threshold,
+                    value,
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Restores and decrypts the master secret from enough members of enough groups
+pub fn decode(shares: &[Slip39Share], passphrase: &str) -> Result<Vec<u8>> {
+    validate_passphrase(passphrase)?;
+    let Some(first) = shares.first() else {
+        return Err(SsssErr::InsufficientShards { needed: 1, got: 0 });
+    };
+
+    let mut groups: Vec<Vec<&Slip39Share>> = vec![vec![]; MAX_SHARE_COUNT as usize];
+    for share in shares {
+        if share.common() != first.common() {
+            return Err(SsssErr::MixedShardSets);
+        }
+        let group = &mut groups[share.group_index as usize];
+        if group.iter().any(|s| s.member_threshold != share.member_threshold) {
+            return Err(SsssErr::MixedShardSets);
+        }
+        if group.iter().any(|s| s.member_index == share.member_index) {
+            return Err(SsssErr::DuplicateShard(share.member_index.into()));
+        }
+        group.push(share);
+    }
+
+    let complete: Vec<(u8, Vec<u8>)> = groups
+        .iter()
+        .filter(|members| members.first().is_some_and(|m| members.len() >= m.member_threshold as usize))
+        .map(|members| {
+            let threshold = members[0].member_threshold;
+            let points: Vec<(u8, Vec<u8>)> =
+                members[..threshold as usize].iter().map(|m| (m.member_index, m.value.clone())).collect();
+            Ok((members[0].group_index, recover_secret(threshold, &points)?))
+        })
+        .collect::<Result<_>>()?;
+
+    if complete.len() < first.group_threshold as usize {
+        // Point out the group closest to completion, if any
+        let incomplete = groups
+            .iter()
+            .filter(|members| members.first().is_some_and(|m| members.len() < m.member_threshold as usize))
+            .max_by_key(|members| members.len());
+        return Err(match incomplete {
+            Some(members) => SsssErr::InsufficientShards { needed: members[0].member_threshold as usize, got: members.len() },
+            None => SsssErr::InsufficientShards { needed: first.group_threshold as usize, got: complete.len() },
+        });
+    }
+
+    let encrypted = recover_secret(first.group_threshold, &complete[..first.group_threshold as usize])?;
+    Ok(cipher::decrypt(&encrypted, passphrase.as_bytes(), first.iteration_exponent, first.identifier, first.extendable))
+}
+
+impl fmt::Display for Slip39Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<&str> = self.to_words().iter().map(|&w| words()[w as usize]).collect();
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+impl str::FromStr for Slip39Share {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let data = s
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| lookup(word, i + 1))
+            .collect::<Result<Vec<u16>>>()?;
+
+        let min_words = HEADER_WORDS + (MIN_SECRET_LEN * 8).div_ceil(RADIX_BITS) + CHECKSUM_WORDS;
+        if data.len() < min_words {
+            return Err(SsssErr::MalformedShard(format!("a mnemonic has at least {min_words} words")));
+        }
+
+        let extendable = (data[1] >> 4) & 1 == 1;
+        if !rs1024_verify(customization(extendable), &data) {
+            return Err(SsssErr::InvalidChecksum);
+        }
+
+        let id_exp = (data[0] as u32) << RADIX_BITS | data[1] as u32;
+        let params = (data[2] as u32) << RADIX_BITS | data[3] as u32;
+        let nibble = |shift: u32| ((params >> shift) & 0xf) as u8;
+
+        let value_words = &data[HEADER_WORDS..data.len() - CHECKSUM_WORDS];
+        let value_bits = value_words.len() * RADIX_BITS;
+        let padding = value_bits % 16;
+        if padding > 8 {
+            return Err(SsssErr::MalformedShard("invalid mnemonic length".into()));
+        }
+
+        let bits: Vec<bool> = value_words
+            .iter()
+            .flat_map(|&word| (0..RADIX_BITS).rev().map(move |i| (word >> i) & 1 == 1))
+            .collect();
+        if bits[..padding].iter().any(|&bit| bit) {
+            return Err(SsssErr::MalformedShard("invalid mnemonic padding".into()));
+        }
+        let value = bits[padding..].chunks(8).map(|byte| byte.iter().fold(0u8, |acc, &bit| acc << 1 | bit as u8)).collect();
+
+        let share = Slip39Share {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_index: nibble(16),
+            group_threshold: nibble(12) + 1,
+            group_count: nibble(8) + 1,
+            member_index: nibble(4),
+            member_threshold: nibble(0) + 1,
+            value,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(SsssErr::MalformedShard("the group threshold exceeds the group count".into()));
+        }
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn parse_all(mnemonics: &[&str]) -> Vec<Slip39Share> {
+        mnemonics.iter().map(|m| m.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn wordlist() {
+        let words = words();
+
+        assert_eq!(1024, words.len());
+        assert!(words.windows(2).all(|w| w[0] < w[1]));
+        assert!(words.windows(2).all(|w| w[0].get(..4) != w[1].get(..4)));
+        assert_eq!(("academic", "zero"), (words[0], words[1023]));
+    }
+
+    /// The official SLIP-39 test vectors, as `[description, mnemonics, master secret]` with the
+    /// master secret empty for sets that must be rejected; all use the passphrase "TREZOR"
+    const VECTORS: &str = include_str!("vectors.json");
+
+    #[test]
+    fn official_vectors() {
+        let vectors: Vec<(String, Vec<String>, String)> = serde_json::from_str(VECTORS).unwrap();
+
+        for (description, mnemonics, master_secret) in vectors {
+            let shares: Result<Vec<Slip39Share>> = mnemonics.iter().map(|m| m.parse()).collect();
+            if master_secret.is_empty() {
+                assert!(shares.and_then(|shares| decode(&shares, "TREZOR")).is_err(), "{description}");
+                continue;
+            }
+
+            let shares = shares.unwrap();
+            assert_eq!(master_secret, hex(&decode(&shares, "TREZOR").unwrap()), "{description}");
+            for (share, mnemonic) in shares.iter().zip(&mnemonics) {
+                assert_eq!(*mnemonic, share.to_string(), "{description}");
+            }
+        }
+    }
+
+    #[test]
+    fn official_invalid_vectors() {
+        let vectors: Vec<(String, Vec<String>, String)> = serde_json::from_str(VECTORS).unwrap();
+        let error = |description: &str| {
+            let (_, mnemonics, _) = vectors.iter().find(|(d, ..)| d == description).unwrap();
+            let shares = parse_all(&mnemonics.iter().map(String::as_str).collect::<Vec<_>>());
+            decode(&shares, "TREZOR").unwrap_err()
+        };
+
+        assert_eq!(SsssErr::MixedShardSets, error("Mnemonics with different identifiers (128 bits)"));
+        assert_eq!(SsssErr::MixedShardSets, error("Mnemonics with different iteration exponents (128 bits)"));
+        assert_eq!(SsssErr::MixedShardSets, error("Mnemonics with mismatching group thresholds (128 bits)"));
+        assert_eq!(SsssErr::MixedShardSets, error("Mnemonics with mismatching group counts (128 bits)"));
+        assert_eq!(SsssErr::MixedShardSets, error("Mnemonics with mismatching member thresholds (128 bits)"));
+        assert_eq!(SsssErr::DuplicateShard(2), error("Mnemonics with duplicate member indices (128 bits)"));
+        assert_eq!(SsssErr::IntegrityCheckFailed, error("Mnemonics giving an invalid digest (128 bits)"));
+        assert_eq!(
+            SsssErr::InsufficientShards { needed: 2, got: 1 },
+            error("Insufficient number of groups (128 bits, case 2)")
+        );
+        assert_eq!(
+            SsssErr::InsufficientShards { needed: 2, got: 1 },
+            error("Threshold number of groups, but insufficient number of members in one group (128 bits)")
+        );
+
+        assert_eq!(
+            Err(SsssErr::InvalidChecksum),
+            "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney"
+                .parse::<Slip39Share>()
+        );
+        assert!(matches!(
+            "duckling enlarge academic academic email result length solution fridge kidney coal piece deal husband erode duke ajar music cargo fitness".parse::<Slip39Share>(),
+            Err(SsssErr::MalformedShard(_))
+        ));
+        assert!(matches!(
+            "music husband acrobat acid artist finance center either graduate swimming object bike medical clothes station aspect spider maiden bulb welcome".parse::<Slip39Share>(),
+            Err(SsssErr::MalformedShard(_))
+        ));
+    }
+
+    #[test]
+    fn two_level_round_trip() {
+        let secret = b"0123456789abcdef0123456789abcdef";
+        let options = Slip39Scheme::new(2, &[(1, 1), (2, 3), (3, 5)]).with_passphrase("hunter2").with_iteration_exponent(0);
+        let groups = encode(&options, secret).unwrap();
+
+        assert_eq!(vec![1, 3, 5], groups.iter().map(Vec::len).collect::<Vec<_>>());
+
+        let text: Vec<String> = [&groups[0][0], &groups[2][4], &groups[2][1], &groups[2][2]].iter().map(|s| s.to_string()).collect();
+        let shares = parse_all(&text.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(secret.to_vec(), decode(&shares, "hunter2").unwrap());
+
+        let shares = [groups[1][2].clone(), groups[1][0].clone(), groups[2][0].clone(), groups[2][3].clone(), groups[2][1].clone()];
+        assert_eq!(secret.to_vec(), decode(&shares, "hunter2").unwrap());
+
+        // A different passphrase yields a different, valid looking secret
+        assert_ne!(secret.to_vec(), decode(&shares, "").unwrap());
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 3, got: 2 }), decode(&shares[..4], "hunter2"));
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 2, got: 1 }), decode(&shares[..2], "hunter2"));
+    }
+
+    #[test]
+    fn not_extendable() {
+        let secret = [7u8; 16];
+        let options = Slip39Scheme::new(1, &[(2, 2)]).with_iteration_exponent(0).without_extendable_backup();
+        let groups = encode(&options, &secret).unwrap();
+        let shares = parse_all(&[&groups[0][1].to_string(), &groups[0][0].to_string()]);
+
+        assert_eq!(secret.to_vec(), decode(&shares, "").unwrap());
+    }
+
+    #[test]
+    fn corrupted_share_fails_digest() {
+        let secret = [1u8; 16];
+        let groups = encode(&Slip39Scheme::new(1, &[(2, 3)]).with_iteration_exponent(0), &secret).unwrap();
+        let mut shares = vec![groups[0][0].clone(), groups[0][2].clone()];
+        shares[1].value[3] ^= 0x40;
+
+        assert_eq!(Err(SsssErr::IntegrityCheckFailed), decode(&shares, ""));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(SsssErr::InvalidSecretLength(15)), encode(&Slip39Scheme::new(1, &[(1, 1)]), &[0; 15]));
+        assert_eq!(Err(SsssErr::InvalidSecretLength(17)), encode(&Slip39Scheme::new(1, &[(1, 1)]), &[0; 17]));
+        assert_eq!(
+            Err(SsssErr::InvalidThreshold { threshold: 1, num_shards: 3 }),
+            encode(&Slip39Scheme::new(1, &[(1, 3)]), &[0; 16])
+        );
+        assert_eq!(
+            Err(SsssErr::InvalidThreshold { threshold: 3, num_shards: 2 }),
+            encode(&Slip39Scheme::new(3, &[(1, 1), (1, 1)]), &[0; 16])
+        );
+        assert_eq!(
+            Err(SsssErr::InvalidPassphrase),
+            encode(&Slip39Scheme::new(1, &[(1, 1)]).with_passphrase("ünicode"), &[0; 16])
+        );
+
+        assert!(matches!("duckling enlarge academic".parse::<Slip39Share>(), Err(SsssErr::MalformedShard(_))));
+        assert_eq!(
+            Err(SsssErr::UnknownWord { position: 1, word: "duckking".into(), suggestions: vec!["duckling".into()] }),
+            "duckking enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"
+                .parse::<Slip39Share>()
+        );
+    }
+}
@@ -0,0 +1,504 @@
+//! Shares of B. Poettering's `ssss-split` and `ssss-combine`
+//!
+//! The secret is read as a big-endian element of GF(2^n), where `n` is the security level: eight
+//! times the secret length unless chosen explicitly. For `n >= 64` the element is first run
+//! through a diffusion layer of XTEA rounds, which `-D` turns off. Shares are the values of the
+//! monic polynomial `x^t + c[t-1] x^(t-1) + ... + c[0]`, with `c[0]` the secret, written as
+//! `[token-]number-hex`.
+//!
+//! Like `ssss-combine`, [`decode`] expects exactly `threshold` shares: the format does not record
+//! the threshold, and it is needed to strip the leading term.
+
+use crate::err::{Result, SsssErr};
+use crate::math::{Gf2n, Gf2nElement};
+use rand::Rng;
+use std::{fmt, str};
+
+/// Largest security level `ssss` supports, in bits
+const MAX_DEGREE: usize = 1024;
+
+/// Longest token `ssss-split -w` accepts
+const MAX_TOKEN_LEN: usize = 128;
+
+/// Middle exponents of the pentanomial reducing GF(2^(8 * (i + 1))), as used by `ssss`
+const IRREDUCIBLE_TAPS: [[usize; 3]; MAX_DEGREE / 8] = [
+    [4, 3, 1], [5, 3, 1], [4, 3, 1], [7, 3, 2], [5, 4, 3], [5, 3, 2], [7, 4, 2], [4, 3, 1],
+    [10, 9, 3], [9, 4, 2], [7, 6, 2], [10, 9, 6], [4, 3, 1], [5, 4, 3], [4, 3, 1], [7, 2, 1],
+    [5, 3, 2], [7, 4, 2], [6, 3, 2], [5, 3, 2], [15, 3, 2], [11, 3, 2], [9, 8, 7], [7, 2, 1],
+    [5, 3, 2], [9, 3, 1], [7, 3, 1], [9, 8, 3], [9, 4, 2], [8, 5, 3], [15, 14, 10], [10, 5, 2],
+    [9, 6, 2], [9, 3, 2], [9, 5, 2], [11, 10, 1], [7, 3, 2], [11, 2, 1], [9, 7, 4], [4, 3, 1],
+    [8, 3, 1], [7, 4, 1], [7, 2, 1], [13, 11, 6], [5, 3, 2], [7, 3, 2], [8, 7, 5], [12, 3, 2],
+    [13, 10, 6], [5, 3, 2], [5, 3, 2], [9, 5, 2], [9, 7, 2], [13, 4, 3], [4, 3, 1], [11, 6, 4],
+    [18, 9, 6], [19, 18, 13], [11, 3, 2], [15, 9, 6], [4, 3, 1], [16, 5, 2], [15, 14, 6], [8, 5, 2],
+    [15, 11, 2], [11, 6, 2], [7, 5, 3], [8, 3, 1], [19, 16, 9], [11, 9, 6], [15, 7, 6], [13, 4, 3],
+    [14, 13, 3], [13, 6, 3], [9, 5, 2], [19, 13, 6], [19, 10, 3], [11, 6, 5], [9, 2, 1], [14, 3, 2],
+    [13, 3, 1], [7, 5, 4], [11, 9, 8], [11, 6, 5], [23, 16, 9], [19, 14, 6], [23, 10, 2], [8, 3, 2],
+    [5, 4, 3], [9, 6, 4], [4, 3, 2], [13, 8, 6], [13, 11, 1], [13, 10, 3], [11, 6, 5], [19, 17, 4],
+    [15, 14, 7], [13, 9, 6], [9, 7, 3], [9, 7, 1], [14, 3, 2], [11, 8, 2], [11, 6, 4], [13, 5, 2],
+    [11, 5, 1], [11, 4, 1], [19, 10, 3], [21, 10, 6], [13, 3, 1], [15, 7, 5], [19, 18, 10], [7, 5, 3],
+    [12, 7, 2], [7, 5, 1], [14, 9, 6], [10, 3, 2], [15, 13, 12], [12, 11, 9], [16, 9, 7], [12, 9, 3],
+    [9, 5, 2], [17, 10, 6], [24, 9, 3], [17, 15, 13], [5, 4, 3], [19, 17, 8], [15, 6, 3], [19, 6, 1],
+];
+
+/// Options for a split that `ssss-combine` can restore
+pub struct ClassicScheme {
+    threshold: u8,
+    num_shards: u8,
+    token: Option<String>,
+    diffusion: bool,
+    security_level: Option<usize>,
+}
+
+impl ClassicScheme {
+    #[must_use]
+    pub fn new(threshold: u8, num_shards: u8) -> Self {
+        ClassicScheme { threshold, num_shards, token: None, diffusion: true, security_level: None }
+    }
+
+    /// Prefixes every share with `token`, like `ssss-split -w`
+    #[must_use]
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_owned());
+        self
+    }
+
+    /// Skips the diffusion layer, like `ssss-split -D`
+    #[must_use]
+    pub fn without_diffusion(mut self) -> Self {
+        self.diffusion = false;
+        self
+    }
+
+    /// Works in GF(2^bits) rather than choosing the field by the secret length, like `ssss-split -s`
+    #[must_use]
+    pub fn with_security_level(mut self, bits: usize) -> Self {
+        self.security_level = Some(bits);
+        self
+    }
+}
+
+/// One share as written by `ssss-split`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassicShard {
+    token: Option<String>,
+    number: u8,
+    /// Digits the number is zero-padded to
+    width: usize,
+    /// Big-endian value, `degree / 8` bytes
+    value: Vec<u8>,
+}
+
+impl ClassicShard {
+    pub fn number(&self) -> u8 { self.number }
+    pub fn token(&self) -> Option<&str> { self.token.as_deref() }
+
+    /// Security level of the split, in bits
+    pub fn degree(&self) -> usize { self.value.len() * 8 }
+}
+
+/// Splits `secret` into shares that `ssss-combine` restores, given `options.threshold` of them
+pub fn encode(options: &ClassicScheme, secret: &[u8]) -> Result<Vec<ClassicShard>> {
+    if options.threshold < 2 || options.threshold > options.num_shards {
+        return Err(SsssErr::InvalidThreshold { threshold: options.threshold.into(), num_shards: options.num_shards.into() });
+    }
+    if secret.is_empty() {
+        return Err(SsssErr::EmptySecret);
+    }
+    if let Some(token) = &options.token
+        && (token.is_empty() || token.len() > MAX_TOKEN_LEN || token.contains('-'))
+    {
+        return Err(SsssErr::InvalidToken(token.clone()));
+    }
+
+    let degree = options.security_level.unwrap_or(secret.len() * 8);
+    let field = field(degree).ok_or(SsssErr::InvalidSecurityLevel(degree))?;
+
+    let mut element = field.import(secret)?;
+    if options.diffusion {
+        element = diffuse(&field, &element, Direction::Encode);
+    }
+
+    let mut coefficients = vec![element];
+    for _ in 1..options.threshold {
+        let mut bytes = vec![0; degree / 8];
+        rand::rng().fill_bytes(&mut bytes);
+        coefficients.push(field.import(&bytes)?);
+    }
+
+    let width = options.num_shards.to_string().len();
+    Ok((1..=options.num_shards)
+        .map(|number| ClassicShard {
+            token: options.token.clone(),
+            number,
+            width,
+            value: field.export(&evaluate(&field, &coefficients, number)),
+        })
+        .collect())
+}
+
+/// Restores the secret from exactly `threshold` shares, as `ssss-combine` does
+///
+/// `diffusion` must be false for shares made with `ssss-split -D`. Like `ssss-combine`, leading
+/// zero bytes of the secret are dropped.
+pub fn decode(shards: &[ClassicShard], diffusion: bool) -> Result<Vec<u8>> {
+    let Some(first) = shards.first() else {
+        return Err(SsssErr::InsufficientShards { needed: 2, got: 0 });
+    };
+
+    let mut seen = [false; 256];
+    for s in shards {
+        if s.token != first.token {
+            return Err(SsssErr::MixedShardSets);
+        }
+        if s.number == 0 {
+            return Err(SsssErr::InvalidShardNumber(s.number.into()));
+        }
+        if seen[s.number as usize] {
+            return Err(SsssErr::DuplicateShard(s.number.into()));
+        }
+        seen[s.number as usize] = true;
+
+        if s.value.len() != first.value.len() {
+            return Err(SsssErr::MismatchedShardLength { expected: first.value.len(), found: s.value.len() });
+        }
+    }
+
+    let degree = first.degree();
+    let field = field(degree).ok_or(SsssErr::InvalidSecurityLevel(degree))?;
+    let threshold = shards.len() as u32;
+
+    // Strip the leading x^t term, then interpolate at zero
+    let points = shards
+        .iter()
+        .map(|s| {
+            let x = field.element(s.number as u64);
+            let y = field.add(&field.import(&s.value)?, &power(&field, &x, threshold));
+            Ok((x, y))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Sum the Lagrange terms as one fraction, so only a single (slow) inversion is needed
+    let mut numerator_sum = field.zero();
+    let mut denominator_product = field.element(1);
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let mut numerator = yi.clone();
+        let mut denominator = field.element(1);
+        for (m, (xm, _)) in points.iter().enumerate() {
+            if i == m { continue; }
+            numerator = field.mul(&numerator, xm);
+            denominator = field.mul(&denominator, &field.add(xi, xm));
+        }
+        numerator_sum = field.add(
+            &field.mul(&numerator_sum, &denominator),
+            &field.mul(&numerator, &denominator_product),
+        );
+        denominator_product = field.mul(&denominator_product, &denominator);
+    }
+    let mut element = field.mul(&numerator_sum, &field.invert(&denominator_product)?);
+
+    if diffusion {
+        element = diffuse(&field, &element, Direction::Decode);
+    }
+
+    let bytes = field.export(&element);
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    Ok(bytes[start..].to_vec())
+}
+
+fn field(degree: usize) -> Option<Gf2n> {
+    if !(8..=MAX_DEGREE).contains(&degree) || !degree.is_multiple_of(8) {
+        return None;
+    }
+    Some(Gf2n::new(degree, IRREDUCIBLE_TAPS[degree / 8 - 1]))
+}
+
+/// Value at `x` of the monic polynomial with the given lower coefficients, by Horner's rule
+fn evaluate(field: &Gf2n, coefficients: &[Gf2nElement], x: u8) -> Gf2nElement {
+    let x = field.element(x as u64);
+    let mut y = x.clone();
+    for c in coefficients[1..].iter().rev() {
+        y = field.mul(&field.add(&y, c), &x);
+    }
+    field.add(&y, &coefficients[0])
+}
+
+fn power(field: &Gf2n, x: &Gf2nElement, exponent: u32) -> Gf2nElement {
+    (0..exponent).fold(field.element(1), |acc, _| field.mul(&acc, x))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Encode,
+    Decode,
+}
+
+/// The diffusion layer of `ssss`: overlapping XTEA encryptions under an all-zero key
+///
+/// `ssss` lays the element out as 16-bit big-endian words, least significant word first, and
+/// moves the lone byte of the top word down when the degree is an odd number of bytes.
+fn diffuse(field: &Gf2n, element: &Gf2nElement, direction: Direction) -> Gf2nElement {
+    let degree = field.degree();
+    if degree < 64 {
+        return element.clone();
+    }
+
+    let len = degree / 8;
+    let bytes = field.export(element);
+    let byte = |k: usize| if k < len { bytes[len - 1 - k] } else { 0 };
+
+    let mut v = vec![0u8; (degree + 8) / 16 * 2];
+    for w in 0..v.len() / 2 {
+        v[2 * w] = byte(2 * w + 1);
+        v[2 * w + 1] = byte(2 * w);
+    }
+    if len % 2 == 1 {
+        v[len - 1] = v[len];
+    }
+
+    let rounds = 40 * len;
+    match direction {
+        Direction::Encode => (0..rounds).step_by(2).for_each(|i| encode_slice(&mut v, i, len, encipher_block)),
+        Direction::Decode => (0..rounds).step_by(2).rev().for_each(|i| encode_slice(&mut v, i, len, decipher_block)),
+    }
+
+    if len % 2 == 1 {
+        v[len] = v[len - 1];
+        v[len - 1] = 0;
+    }
+
+    let mut out = vec![0u8; len];
+    for w in 0..v.len() / 2 {
+        for (k, b) in [(2 * w + 1, v[2 * w]), (2 * w, v[2 * w + 1])] {
+            if k < len {
+                out[len - 1 - k] = b;
+            }
+        }
+    }
+    field.import(&out).expect("diffusion preserves the length")
+}
+
+/// Runs one XTEA block over the eight bytes at `idx`, wrapping around at `len`
+fn encode_slice(data: &mut [u8], idx: usize, len: usize, process_block: fn(&mut [u32; 2])) {
+    let mut v = [0u32; 2];
+    for (i, word) in v.iter_mut().enumerate() {
+        *word = (0..4).fold(0, |acc, j| acc << 8 | data[(idx + 4 * i + j) % len] as u32);
+    }
+
+    process_block(&mut v);
+
+    for (i, word) in v.iter().enumerate() {
+        for j in 0..4 {
+            data[(idx + 4 * i + j) % len] = (word >> (24 - 8 * j)) as u8;
+        }
+    }
+}
+
+const XTEA_DELTA: u32 = 0x9E37_79B9;
+
+fn encipher_block(v: &mut [u32; 2]) {
+    let mut sum = 0u32;
+    for _ in 0..32 {
+        v[0] = v[0].wrapping_add(((v[1] << 4 ^ v[1] >> 5).wrapping_add(v[1])) ^ sum);
+        sum = sum.wrapping_add(XTEA_DELTA);
+        v[1] = v[1].wrapping_add(((v[0] << 4 ^ v[0] >> 5).wrapping_add(v[0])) ^ sum);
+    }
+}
+
+fn decipher_block(v: &mut [u32; 2]) {
+    let mut sum = XTEA_DELTA.wrapping_mul(32);
+    for _ in 0..32 {
+        v[1] = v[1].wrapping_sub(((v[0] << 4 ^ v[0] >> 5).wrapping_add(v[0])) ^ sum);
+        sum = sum.wrapping_sub(XTEA_DELTA);
+        v[0] = v[0].wrapping_sub(((v[1] << 4 ^ v[1] >> 5).wrapping_add(v[1])) ^ sum);
+    }
+}
+
+impl fmt::Display for ClassicShard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(token) = &self.token {
+            write!(f, "{token}-")?;
+        }
+        write!(f, "{:0width$}-", self.number, width = self.width)?;
+        for b in &self.value {
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for ClassicShard {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split: Vec<&str> = s.rsplitn(3, '-').collect();
+        if split.len() < 2 {
+            return Err(SsssErr::MalformedShard("expected `[<token>-]<number>-<hex data>`".into()));
+        }
+
+        let hex = split[0];
+        let number = split[1];
+        let token = split.get(2).map(|t| t.to_string());
+
+        if !hex.is_ascii() || !hex.len().is_multiple_of(2) || field(hex.len() * 4).is_none() {
+            return Err(SsssErr::MalformedShard("share has an illegal length".into()));
+        }
+        let value = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<std::result::Result<Vec<u8>, _>>()
+            .map_err(|_| SsssErr::MalformedShard("invalid hex data".into()))?;
+
+        Ok(ClassicShard {
+            token,
+            number: number.parse().map_err(|_| SsssErr::MalformedShard("invalid shard number".into()))?,
+            width: number.len(),
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn parse_all(shares: &[&str]) -> Vec<ClassicShard> {
+        shares.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn xtea_zero_key_vector() {
+        let mut v = [0, 0];
+        encipher_block(&mut v);
+        assert_eq!([0xdee9_d4d8, 0xf713_1ed9], v);
+
+        decipher_block(&mut v);
+        assert_eq!([0, 0], v);
+    }
+
+    /// The example from the `ssss` man page: `ssss-split -t 3 -n 5` of "my secret root password",
+    /// at the dynamic 184 bit security level and with diffusion
+    const MAN_PAGE_SHARES: [&str; 5] = [
+        "1-1c41ef496eccfbeba439714085df8437236298da8dd824",
+        "2-fbc74a03a50e14ab406c225afb5f45c40ae11976d2b665",
+        "3-fa1c3a9c6df8af0779c36de6c33f6e36e989d0e0b91309",
+        "4-468de7d6eb36674c9cf008c8e8fc8c566537ad6301eb9e",
+        "5-4756974923c0dce0a55f4774d09ca7a4865f64f56a4ee0",
+    ];
+
+    #[test_case(&[0, 1, 2] ; "first three")]
+    #[test_case(&[2, 4, 0] ; "as in the man page")]
+    #[test_case(&[4, 3, 1] ; "last three reversed")]
+    fn ssss_split_shares(picked: &[usize]) {
+        let shares: Vec<&str> = picked.iter().map(|&i| MAN_PAGE_SHARES[i]).collect();
+        let shards = parse_all(&shares);
+
+        assert_eq!(184, shards[0].degree());
+        assert_eq!(b"my secret root password".to_vec(), decode(&shards, true).unwrap());
+
+        // `ssss-combine -D` would return the diffused secret, which the diffusion layer undoes
+        let diffused = decode(&shards, false).unwrap();
+        let field = field(184).unwrap();
+        let undiffused = diffuse(&field, &field.import(&diffused).unwrap(), Direction::Decode);
+        assert_ne!(b"my secret root password".to_vec(), diffused);
+        assert_eq!(b"my secret root password".to_vec(), field.export(&undiffused));
+
+        // `ssss-split -w` only prefixes the shares with the token
+        let tokened: Vec<String> = shares.iter().map(|s| format!("root-{s}")).collect();
+        let shards = parse_all(&tokened.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(Some("root"), shards[0].token());
+        assert_eq!(b"my secret root password".to_vec(), decode(&shards, true).unwrap());
+    }
+
+    #[test]
+    fn hand_computed_shares() {
+        // GF(2^8) with the AES polynomial, too small for diffusion; secret 'A', c[1] = 0x10:
+        // y(1) = (1 + 0x10) * 1 + 0x41 = 0x50, y(2) = (2 + 0x10) * 2 + 0x41 = 0x65
+        let field = field(8).unwrap();
+        let coefficients = [field.element(0x41), field.element(0x10)];
+
+        assert_eq!(vec![0x50], field.export(&evaluate(&field, &coefficients, 1)));
+        assert_eq!(vec![0x65], field.export(&evaluate(&field, &coefficients, 2)));
+        assert_eq!(b"A".to_vec(), decode(&parse_all(&["1-50", "2-65"]), false).unwrap());
+        assert_eq!(b"A".to_vec(), decode(&parse_all(&["2-65", "1-50"]), true).unwrap());
+    }
+
+    #[test_case(8, 2, 3 ; "one byte")]
+    #[test_case(64, 3, 5 ; "diffused eight bytes")]
+    #[test_case(72, 3, 5 ; "diffused odd bytes")]
+    #[test_case(1024, 4, 9 ; "largest field")]
+    fn round_trip(degree: usize, threshold: u8, num_shards: u8) {
+        let secret: Vec<u8> = (1..=(degree / 8) as u8).collect();
+
+        for diffusion in [true, false] {
+            let mut options = ClassicScheme::new(threshold, num_shards);
+            if !diffusion {
+                options = options.without_diffusion();
+            }
+            let shards = encode(&options, &secret).unwrap();
+            let text: Vec<String> = shards.iter().map(ToString::to_string).collect();
+            let parsed = parse_all(&text.iter().map(String::as_str).collect::<Vec<_>>());
+
+            assert_eq!(shards, parsed);
+            assert_eq!(secret, decode(&parsed[..threshold as usize], diffusion).unwrap());
+            assert_eq!(secret, decode(&parsed[num_shards as usize - threshold as usize..], diffusion).unwrap());
+            if degree >= 64 {
+                assert_ne!(secret, decode(&parsed[..threshold as usize], !diffusion).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn diffusion_is_a_permutation() {
+        for degree in [64, 72, 136] {
+            let field = field(degree).unwrap();
+            let element = field.import(b"diffuse").unwrap();
+            let diffused = diffuse(&field, &element, Direction::Encode);
+
+            assert_ne!(element, diffused);
+            assert_eq!(element, diffuse(&field, &diffused, Direction::Decode));
+        }
+    }
+
+    #[test]
+    fn token_and_format() {
+        let options = ClassicScheme::new(3, 12).with_token("backup").with_security_level(128);
+        let shards = encode(&options, b"short").unwrap();
+        let text = shards[3].to_string();
+
+        assert!(text.starts_with("backup-04-"));
+        assert_eq!(10 + 32, text.len());
+        assert_eq!(Some("backup"), shards[3].token());
+        assert_eq!(128, shards[3].degree());
+        assert_eq!(b"short".to_vec(), decode(&shards[4..7], true).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(SsssErr::InvalidThreshold { threshold: 1, num_shards: 3 }), encode(&ClassicScheme::new(1, 3), b"x"));
+        assert_eq!(Err(SsssErr::EmptySecret), encode(&ClassicScheme::new(2, 3), b""));
+        assert_eq!(
+            Err(SsssErr::InvalidSecurityLevel(12)),
+            encode(&ClassicScheme::new(2, 3).with_security_level(12), b"x")
+        );
+        assert_eq!(
+            Err(SsssErr::SecretTooLong { max: 2 }),
+            encode(&ClassicScheme::new(2, 3).with_security_level(16), b"xyz")
+        );
+        assert_eq!(
+            Err(SsssErr::InvalidToken("a-b".into())),
+            encode(&ClassicScheme::new(2, 3).with_token("a-b"), b"x")
+        );
+
+        assert_eq!(Err(SsssErr::DuplicateShard(1)), decode(&parse_all(&["1-50", "1-65"]), false));
+        assert_eq!(Err(SsssErr::MixedShardSets), decode(&parse_all(&["a-1-50", "b-2-65"]), false));
+        assert_eq!(
+            Err(SsssErr::MismatchedShardLength { expected: 1, found: 2 }),
+            decode(&parse_all(&["1-50", "2-6565"]), false)
+        );
+        assert!(matches!("1-5".parse::<ClassicShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("1-zz".parse::<ClassicShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("x-50".parse::<ClassicShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("50".parse::<ClassicShard>(), Err(SsssErr::MalformedShard(_))));
+    }
+}
@@ -0,0 +1,226 @@
+//! Unseal and recovery key shares of HashiCorp Vault's `shamir` package
+//!
+//! Vault shares every byte over the same GF(2^8) as [`ShamirScheme`](crate::ShamirScheme), but
+//! hands out shares at distinct random x-coordinates, drawn from a permutation of 1 to 255 and
+//! appended as the trailing byte of each share. Shares are displayed in standard base64.
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::err::{Result, SsssErr};
+use crate::math::GfPoly;
+use crate::Reconstructor;
+use rand::RngExt;
+use rand::seq::SliceRandom;
+use std::{fmt, str};
+
+/// Options for a split that Vault can combine
+pub struct VaultScheme {
+    threshold: u8,
+    num_shards: u8,
+}
+
+impl VaultScheme {
+    #[must_use]
+    pub fn new(threshold: u8, num_shards: u8) -> Self {
+        VaultScheme { threshold, num_shards }
+    }
+}
+
+/// One share in Vault's layout: the share bytes followed by the x-coordinate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultShard {
+    data: Vec<u8>,
+}
+
+impl VaultShard {
+    /// Wraps the raw bytes of a share, as Vault's `shamir.Split` returns them
+    pub fn from_bytes(data: Vec<u8>) -> Result<VaultShard> {
+        if data.len() < 2 {
+            return Err(SsssErr::MalformedShard("a Vault share holds at least two bytes".into()));
+        }
+        Ok(VaultShard { data })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] { &self.data }
+
+    /// The x-coordinate of the share
+    pub fn x(&self) -> u8 { self.data[self.data.len() - 1] }
+
+    fn values(&self) -> &[u8] { &self.data[..self.data.len() - 1] }
+}
+
+/// Splits `secret` like Vault's `shamir.Split(secret, num_shards, threshold)`
+pub fn encode(options: &VaultScheme, secret: &[u8]) -> Result<Vec<VaultShard>> {
+    if options.threshold < 2 || options.threshold > options.num_shards {
+        return Err(SsssErr::InvalidThreshold { threshold: options.threshold.into(), num_shards: options.num_shards.into() });
+    }
+    if secret.is_empty() {
+        return Err(SsssErr::EmptySecret);
+    }
+
+    let mut rng = rand::rng();
+    let mut x_coordinates: Vec<u8> = (1..=255).collect();
+    x_coordinates.shuffle(&mut rng);
+    x_coordinates.truncate(options.num_shards as usize);
+
+    let mut shares = vec![Vec::with_capacity(secret.len() + 1); options.num_shards as usize];
+    for &byte in secret {
+        let mut coefficients = vec![byte];
+        for _ in 1..options.threshold {
+            coefficients.push(rng.random());
+        }
+        let poly = GfPoly::new(&coefficients);
+
+        for (share, &x) in shares.iter_mut().zip(&x_coordinates) {
+            share.push(poly.apply_x(x).value() as u8);
+        }
+    }
+
+    Ok(shares
+        .into_iter()
+        .zip(x_coordinates)
+        .map(|(mut data, x)| {
+            data.push(x);
+            VaultShard { data }
+        })
+        .collect())
+}
+
+/// Restores the secret like Vault's `shamir.Combine`
+///
+/// Vault's shares do not record the threshold; with fewer shares than it, the result is garbage.
+pub fn decode(shards: &[VaultShard]) -> Result<Vec<u8>> {
+    if shards.len() < 2 {
+        return Err(SsssErr::InsufficientShards { needed: 2, got: shards.len() });
+    }
+
+    let mut seen = [false; 256];
+    for s in shards {
+        if s.data.len() != shards[0].data.len() {
+            return Err(SsssErr::MismatchedShardLength { expected: shards[0].data.len(), found: s.data.len() });
+        }
+        if seen[s.x() as usize] {
+            return Err(SsssErr::DuplicateShard(s.x().into()));
+        }
+        seen[s.x() as usize] = true;
+    }
+
+    let x: Vec<u32> = shards.iter().map(|s| s.x().into()).collect();
+    let values: Vec<&[u8]> = shards.iter().map(VaultShard::values).collect();
+    Reconstructor::new(&x)?.reconstruct(&values)
+}
+
+impl fmt::Display for VaultShard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", base64_encode(&self.data).map_err(|_| fmt::Error)?)
+    }
+}
+
+impl str::FromStr for VaultShard {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        VaultShard::from_bytes(base64_decode(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::GF;
+    use test_case::test_case;
+
+    fn parse_all(shares: &[&str]) -> Vec<VaultShard> {
+        shares.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test_case(3, 7, 9 ; "mult 3 7")]
+    #[test_case(7, 3, 9 ; "mult 7 3")]
+    fn field_mult(a: u8, b: u8, expected: u8) {
+        // From `TestField_Mul` in Vault's shamir package
+        assert_eq!(GF::new(expected), GF::new(a) * GF::new(b));
+    }
+
+    #[test_case(0, 7, 0 ; "zero")]
+    #[test_case(3, 3, 1 ; "one")]
+    #[test_case(6, 3, 2 ; "two")]
+    fn field_divide(a: u8, b: u8, expected: u8) {
+        // From `TestField_Divide` in Vault's shamir package
+        assert_eq!(GF::new(expected), GF::new(a) * GF::new(b).invert().unwrap());
+    }
+
+    #[test]
+    fn hand_computed_shares() {
+        // 'A' + 0x10 x at x = 1 and x = 2, with the coordinate as trailing byte
+        assert_eq!(b"A".to_vec(), decode(&parse_all(&["UQE=", "YQI="])).unwrap());
+        assert_eq!(b"A".to_vec(), decode(&parse_all(&["YQI=", "UQE="])).unwrap());
+    }
+
+    /// The unseal keys `vault operator init` printed in Vault's getting started guide, a split of
+    /// a 32-byte key with threshold three
+    const VAULT_INIT_KEYS: [&str; 5] = [
+        "4jYbl2CBIv6SpkKj6Hos9iD32k5RfGkLzlosrrq/JgOm",
+        "B05G1DRtfYckFV5BbdBvXq0wkK5HFqB9g2jcDmNfTQiS",
+        "Arig0N9rN9ezkTRo7qTB7gsIZDaonOcc53EHo83F5chA",
+        "0cZE0C/gEk3YHaKjIWxhyyfs8REhqkRW/CSXTnmTilv+",
+        "fYhZOseRgzxmJCmIqUdxEm9C3jB5Q27AowER9w4FC2Ck",
+    ];
+    const VAULT_INIT_KEY: [u8; 32] = [
+        0xd2, 0x4e, 0x15, 0xf5, 0xd9, 0x92, 0x3e, 0xf2, 0x40, 0xb2, 0x69, 0xf0, 0x5a, 0xec, 0x3e, 0x3f,
+        0x74, 0x73, 0x1e, 0x9d, 0x43, 0xa8, 0x86, 0xe4, 0x0c, 0x9f, 0xf6, 0xe7, 0x2c, 0x35, 0x85, 0x35,
+    ];
+
+    #[test_case(&[0, 1, 2] ; "first three")]
+    #[test_case(&[4, 2, 0] ; "every other")]
+    #[test_case(&[3, 4, 1] ; "last two and the second")]
+    #[test_case(&[0, 1, 2, 3, 4] ; "all five")]
+    fn vault_operator_init(picked: &[usize]) {
+        let keys: Vec<&str> = picked.iter().map(|&i| VAULT_INIT_KEYS[i]).collect();
+        let shards = parse_all(&keys);
+
+        assert_eq!(VAULT_INIT_KEY.to_vec(), decode(&shards).unwrap());
+        assert_ne!(VAULT_INIT_KEY.to_vec(), decode(&shards[..2]).unwrap());
+    }
+
+    #[test]
+    fn split_and_combine() {
+        // Like `TestCombine` in Vault's shamir package: every subset of three restores the secret
+        let secret = b"test";
+        let shards = encode(&VaultScheme::new(3, 5), secret).unwrap();
+
+        for i in 0..5 {
+            for j in i + 1..5 {
+                for k in j + 1..5 {
+                    let subset = [shards[i].clone(), shards[j].clone(), shards[k].clone()];
+                    assert_eq!(secret.to_vec(), decode(&subset).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let secret = [0u8; 32];
+        let shards = encode(&VaultScheme::new(2, 255), &secret).unwrap();
+
+        let mut x: Vec<u8> = shards.iter().map(VaultShard::x).collect();
+        x.sort();
+        assert_eq!((1..=255).collect::<Vec<u8>>(), x);
+        assert!(shards.iter().all(|s| s.as_bytes().len() == 33));
+        assert_eq!(shards[7], shards[7].to_string().parse().unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(SsssErr::InvalidThreshold { threshold: 1, num_shards: 3 }), encode(&VaultScheme::new(1, 3), b"x"));
+        assert_eq!(Err(SsssErr::InvalidThreshold { threshold: 4, num_shards: 3 }), encode(&VaultScheme::new(4, 3), b"x"));
+        assert_eq!(Err(SsssErr::EmptySecret), encode(&VaultScheme::new(2, 3), b""));
+
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 2, got: 1 }), decode(&parse_all(&["UQE="])));
+        assert_eq!(Err(SsssErr::DuplicateShard(1)), decode(&parse_all(&["UQE=", "YQE="])));
+        assert_eq!(
+            Err(SsssErr::MismatchedShardLength { expected: 2, found: 3 }),
+            decode(&parse_all(&["UQE=", "YWIC"]))
+        );
+        assert!(matches!("UQ==".parse::<VaultShard>(), Err(SsssErr::MalformedShard(_))));
+    }
+}
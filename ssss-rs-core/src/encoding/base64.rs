@@ -1,3 +1,5 @@
+use crate::err::SsssErr;
+
 const PAD_CHAR: char = '=';
 
 /// Translates a byte-array to its corresponding base64 encoding
@@ -37,50 +39,86 @@ pub fn base64_encode(x: &[u8]) -> crate::err::Result<String> {
 }
 
 /// Translates a base64 encoded string to its corresponding byte-array
+///
+/// Decoding is strict: every character must be in the alphabet, the padding must bring the length
+/// to a multiple of four, and the bits left over by the padding must be zero, so that every
+/// byte-array has exactly one accepted encoding.
 pub fn base64_decode(x: &str) -> crate::err::Result<Vec<u8>> {
-    if x.len() == 0 {
-        return Ok(vec![]);
+    decode(x.as_bytes())
+}
+
+/// Like [`base64_decode`], but ignores whitespace, such as the line wraps of PEM or MIME
+///
+/// Positions in errors refer to `x` as given, whitespace included.
+pub fn base64_decode_lenient(x: &str) -> crate::err::Result<Vec<u8>> {
+    let (positions, compact): (Vec<usize>, Vec<u8>) =
+        x.bytes().enumerate().filter(|(_, c)| !c.is_ascii_whitespace()).unzip();
+
+    decode(&compact).map_err(|e| match e {
+        SsssErr::InvalidBase64 { position } => SsssErr::InvalidBase64 {
+            position: positions.get(position).copied().unwrap_or(x.len()),
+        },
+        e => e,
+    })
+}
+
+fn decode(x: &[u8]) -> crate::err::Result<Vec<u8>> {
+    if !x.len().is_multiple_of(4) {
+        return Err(SsssErr::InvalidBase64 { position: x.len() });
     }
-    if x.len() % 4 != 0 {
-        return Err(crate::err::SsssErr);
+
+    let unpadded = x.iter().rposition(|&c| c != PAD_CHAR as u8).map_or(0, |last| last + 1);
+    if x.len() - unpadded > 2 {
+        return Err(SsssErr::InvalidBase64 { position: unpadded });
     }
-    let mut output = Vec::with_capacity((x.len() as f64 * 0.8).ceil() as usize );
+    let values = x[..unpadded]
+        .iter()
+        .enumerate()
+        .map(|(position, &c)| decode_char(c).ok_or(SsssErr::InvalidBase64 { position }))
+        .collect::<crate::err::Result<Vec<u8>>>()?;
 
-    let chunks = x.trim_end_matches('=').as_bytes().chunks_exact(4);
+    let mut output = Vec::with_capacity(values.len() * 3 / 4);
+
+    let chunks = values.chunks_exact(4);
 
     let rem = chunks.remainder();
 
     for chunk in chunks {
-        let a: u8 = DEC_LOOKUP_TABLE[chunk[0] as usize];
-        let b: u8 = DEC_LOOKUP_TABLE[chunk[1] as usize];
-        let c: u8 = DEC_LOOKUP_TABLE[chunk[2] as usize];
-        let d: u8 = DEC_LOOKUP_TABLE[chunk[3] as usize];
+        let (a, b, c, d) = (chunk[0], chunk[1], chunk[2], chunk[3]);
 
-        output.push((a << 2) | ((b & 0xF0) >> 4));
-        output.push(((b & 0x0F) << 4) | ((c & 0x3C) >> 2) );
-        output.push(((c & 0x03) << 6) | (d & 0x3F) );
+        output.push((a << 2) | ((b & 0x30) >> 4));
+        output.push(((b & 0x0F) << 4) | ((c & 0x3C) >> 2));
+        output.push(((c & 0x03) << 6) | d);
     }
 
-    // 3 padding characters cannot happen:
-    // if rem.len() == 1 { ... }
+    // One character holds too few bits for a byte; with at most two padding characters and a
+    // length that is a multiple of four it cannot remain
     if rem.len() == 2 {
-        let a: u8 = DEC_LOOKUP_TABLE[rem[0] as usize];
-        let b: u8 = DEC_LOOKUP_TABLE[rem[1] as usize];
+        let (a, b) = (rem[0], rem[1]);
+        if b & 0x0F != 0 {
+            return Err(SsssErr::InvalidBase64 { position: unpadded - 1 });
+        }
 
-        output.push((a << 2) | ((b & 0xF0) >> 4));
+        output.push((a << 2) | ((b & 0x30) >> 4));
     }
     if rem.len() == 3 {
-        let a: u8 = DEC_LOOKUP_TABLE[rem[0] as usize];
-        let b: u8 = DEC_LOOKUP_TABLE[rem[1] as usize];
-        let c: u8 = DEC_LOOKUP_TABLE[rem[2] as usize];
+        let (a, b, c) = (rem[0], rem[1], rem[2]);
+        if c & 0x03 != 0 {
+            return Err(SsssErr::InvalidBase64 { position: unpadded - 1 });
+        }
 
-        output.push((a << 2) | ((b & 0xF0) >> 4));
-        output.push(((b & 0x0F) << 4) | ((c & 0x3C) >> 2) );
+        output.push((a << 2) | ((b & 0x30) >> 4));
+        output.push(((b & 0x0F) << 4) | ((c & 0x3C) >> 2));
     }
 
     Ok(output)
 }
 
+/// Value of a character of the alphabet; padding and anything else is `None`
+fn decode_char(c: u8) -> Option<u8> {
+    DEC_LOOKUP_TABLE.get(c as usize).copied().filter(|&v| v < 64)
+}
+
 const ENC_LOOKUP_TABLE: [char; 64] = [
     'A','B','C','D','E','F','G','H','I','J','K','L','M','N','O','P','Q','R','S','T','U','V','W','X','Y','Z',
     'a','b','c','d','e','f','g','h','i','j','k','l','m','n','o','p','q','r','s','t','u','v','w','x','y','z',
@@ -125,4 +163,102 @@ mod tests {
         
         Ok(())
     }
+
+    #[test_case("QQ!=", 2)]
+    #[test_case("Q-==", 1)]
+    #[test_case("QUJ", 3)]
+    #[test_case("Q===", 1 ; "three padding characters")]
+    #[test_case("QUJD====", 4 ; "padding quad")]
+    #[test_case("====", 0 ; "only padding")]
+    #[test_case("QQ=A", 2 ; "padding inside")]
+    #[test_case("QR==", 1 ; "trailing bits before two padding characters")]
+    #[test_case("QUJ=", 2 ; "trailing bits before one padding character")]
+    #[test_case("Q\u{e9}=", 1 ; "non-ascii")]
+    fn invalid_input_reports_position(encoded: &str, position: usize) {
+        assert_eq!(Err(SsssErr::InvalidBase64 { position }), base64_decode(encoded));
+    }
+
+    #[test]
+    fn all_short_inputs_round_trip() {
+        for data in (0..=255u8).map(|a| vec![a]).chain((0..=0xffffu16).map(|ab| ab.to_be_bytes().to_vec())) {
+            assert_eq!(data, base64_decode(&base64_encode(&data).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn padded_quads_decode_only_when_canonical() {
+        let alphabet = || ENC_LOOKUP_TABLE.iter();
+
+        for a in alphabet() {
+            for b in alphabet() {
+                let encoded = format!("{a}{b}==");
+                let decoded = base64_decode(&encoded);
+                assert_eq!(decoded.is_ok(), decode_char(*b as u8).unwrap() & 0x0F == 0);
+                assert!(decoded.is_err() || base64_encode(&decoded.unwrap()).unwrap() == encoded);
+
+                for c in alphabet() {
+                    let encoded = format!("{a}{b}{c}=");
+                    let decoded = base64_decode(&encoded);
+                    assert_eq!(decoded.is_ok(), decode_char(*c as u8).unwrap() & 0x03 == 0);
+                    assert!(decoded.is_err() || base64_encode(&decoded.unwrap()).unwrap() == encoded);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_byte_at_every_position() {
+        for position in 0..4 {
+            // Padding has tests of its own
+            for byte in (0..=255u8).filter(|&byte| byte != PAD_CHAR as u8) {
+                let mut encoded = *b"QUJD";
+                encoded[position] = byte;
+                let Ok(encoded) = String::from_utf8(encoded.to_vec()) else {
+                    continue;
+                };
+
+                match decode_char(byte) {
+                    Some(_) => assert_eq!(3, base64_decode(&encoded).unwrap().len()),
+                    None => assert_eq!(Err(SsssErr::InvalidBase64 { position }), base64_decode(&encoded)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lenient_ignores_whitespace() {
+        assert_eq!(b"ABC".to_vec(), base64_decode_lenient(" QU\nJD\r\n").unwrap());
+        assert_eq!(b"AB".to_vec(), base64_decode_lenient("QU\tI=").unwrap());
+        assert_eq!(Err(SsssErr::InvalidBase64 { position: 4 }), base64_decode_lenient("QU\nJ!"));
+        assert_eq!(Err(SsssErr::InvalidBase64 { position: 5 }), base64_decode_lenient("QU\nJ "));
+        assert!(base64_decode("QU\nJD").is_err());
+    }
+
+    #[test]
+    fn fuzz() {
+        use rand::{Rng, RngExt};
+
+        let mut rng = rand::rng();
+        let alphabet: Vec<u8> = ENC_LOOKUP_TABLE.iter().map(|&c| c as u8).chain(*b"=\n -!\xff").collect();
+
+        for _ in 0..10_000 {
+            let mut data = vec![0u8; rng.random_range(0..48)];
+            rng.fill_bytes(&mut data);
+            let encoded = base64_encode(&data).unwrap();
+            assert_eq!(data, base64_decode(&encoded).unwrap());
+
+            let wrapped: String = encoded.chars().flat_map(|c| [c].into_iter().chain(rng.random_bool(0.1).then_some('\n'))).collect();
+            assert_eq!(data, base64_decode_lenient(&wrapped).unwrap());
+
+            // Random text never panics, and whatever decodes is the one canonical encoding
+            let text: Vec<u8> = (0..rng.random_range(0..16)).map(|_| alphabet[rng.random_range(0..alphabet.len())]).collect();
+            let text = String::from_utf8_lossy(&text);
+            if let Ok(decoded) = base64_decode(&text) {
+                assert_eq!(text, base64_encode(&decoded).unwrap());
+            }
+            if let Err(SsssErr::InvalidBase64 { position }) = base64_decode_lenient(&text) {
+                assert!(position <= text.len());
+            }
+        }
+    }
 }
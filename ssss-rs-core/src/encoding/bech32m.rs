@@ -0,0 +1,179 @@
+//! Bech32m of BIP-350: a human-readable part, the separator `1`, the data in a 32 character
+//! alphabet and a six character checksum that detects up to four errors
+
+use super::Encoding;
+use crate::err::{Result, SsssErr};
+
+const ALPHABET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LEN: usize = 6;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+/// Human-readable part used for shard data
+const DEFAULT_HRP: &str = "ssss";
+
+/// Bech32m with a fixed human-readable part, `ssss` by default
+///
+/// BIP-350 limits strings to 90 characters; longer data is encoded all the same, though the
+/// checksum then guarantees less.
+pub struct Bech32m {
+    hrp: String,
+}
+
+impl Bech32m {
+    /// `hrp` must be 1 to 83 printable ASCII characters
+    pub fn new(hrp: &str) -> Self {
+        Bech32m { hrp: hrp.to_lowercase() }
+    }
+}
+
+impl Default for Bech32m {
+    fn default() -> Self {
+        Bech32m::new(DEFAULT_HRP)
+    }
+}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+
+    values.fold(1, |chk, v| {
+        let b = chk >> 25;
+        let chk = (chk & 0x1ff_ffff) << 5 ^ v as u32;
+        GEN.iter().enumerate().filter(|(i, _)| (b >> i) & 1 == 1).fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+fn hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes().map(|c| c >> 5).chain([0]).chain(hrp.bytes().map(|c| c & 31))
+}
+
+fn verify_checksum(hrp: &str, data: &[u8]) -> bool {
+    polymod(hrp_expand(hrp).chain(data.iter().copied())) == BECH32M_CONST
+}
+
+fn create_checksum(hrp: &str, data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let values = hrp_expand(hrp).chain(data.iter().copied()).chain([0; CHECKSUM_LEN]);
+    let polymod = polymod(values) ^ BECH32M_CONST;
+    [0, 1, 2, 3, 4, 5].map(|i| (polymod >> (5 * (5 - i))) as u8 & 31)
+}
+
+/// Regroups bits from `from` to `to` bits per value, padding with zero bits if `pad`
+fn convert_bits(data: &[u8], from: usize, to: usize, pad: bool) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity((data.len() * from).div_ceil(to));
+    let mut accumulator = 0u32;
+    let mut pending = 0;
+
+    for &value in data {
+        accumulator = (accumulator << from | value as u32) & 0xfff;
+        pending += from;
+        while pending >= to {
+            pending -= to;
+            output.push(((accumulator >> pending) & ((1 << to) - 1)) as u8);
+        }
+    }
+
+    if pad {
+        if pending > 0 {
+            output.push(((accumulator << (to - pending)) & ((1 << to) - 1)) as u8);
+        }
+    } else if pending >= from || accumulator & ((1 << pending) - 1) != 0 {
+        return None;
+    }
+    Some(output)
+}
+
+impl Encoding for Bech32m {
+    fn encode(&self, data: &[u8]) -> String {
+        let values = convert_bits(data, 8, 5, true).expect("padding never fails");
+        let checksum = create_checksum(&self.hrp, &values);
+
+        let mut output = format!("{}{SEPARATOR}", self.hrp);
+        output.extend(values.iter().chain(&checksum).map(|&v| ALPHABET[v as usize] as char));
+        output
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        let invalid = |position| SsssErr::InvalidEncoding { encoding: "bech32m", position };
+
+        // Either case is allowed, but not both
+        let has_lower = text.bytes().any(|c| c.is_ascii_lowercase());
+        if let Some(position) = text.bytes().position(|c| has_lower && c.is_ascii_uppercase()) {
+            return Err(invalid(position));
+        }
+        let text = text.to_ascii_lowercase();
+
+        let separator = text.rfind(SEPARATOR).ok_or(invalid(0))?;
+        if text[..separator] != self.hrp {
+            return Err(invalid(0));
+        }
+        if text.len() - separator - 1 < CHECKSUM_LEN {
+            return Err(invalid(text.len()));
+        }
+
+        let values = text[separator + 1..]
+            .bytes()
+            .enumerate()
+            .map(|(i, c)| ALPHABET.iter().position(|&a| a == c).map(|p| p as u8).ok_or(invalid(separator + 1 + i)))
+            .collect::<Result<Vec<u8>>>()?;
+
+        if !verify_checksum(&self.hrp, &values) {
+            return Err(SsssErr::InvalidChecksum);
+        }
+
+        convert_bits(&values[..values.len() - CHECKSUM_LEN], 5, 8, false)
+            .ok_or(invalid(text.len() - CHECKSUM_LEN - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    // Valid Bech32m strings of BIP-350
+    #[test_case("A1LQFN3A" ; "uppercase")]
+    #[test_case("a1lqfn3a" ; "lowercase")]
+    #[test_case("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx" ; "whole alphabet")]
+    #[test_case("split1checkupstagehandshakeupstreamerranterredcaperredlc445v" ; "words")]
+    #[test_case("?1v759aa" ; "punctuation")]
+    fn bip350_checksums(valid: &str) {
+        let lower = valid.to_lowercase();
+        let (hrp, data) = lower.rsplit_once(SEPARATOR).unwrap();
+        let values: Vec<u8> = data.bytes().map(|c| ALPHABET.iter().position(|&a| a == c).unwrap() as u8).collect();
+
+        assert!(verify_checksum(hrp, &values));
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in 0..40 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 73 + 5) as u8).collect();
+            let encoded = Bech32m::default().encode(&data);
+
+            assert!(encoded.starts_with("ssss1"));
+            assert_eq!(data, Bech32m::default().decode(&encoded).unwrap());
+            assert_eq!(data, Bech32m::default().decode(&encoded.to_uppercase()).unwrap());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let encoded = Bech32m::default().encode(b"foobar");
+        let mut typo = encoded.clone().into_bytes();
+        typo[7] = if typo[7] == b'q' { b'p' } else { b'q' };
+
+        assert_eq!(Err(SsssErr::InvalidChecksum), Bech32m::default().decode(str::from_utf8(&typo).unwrap()));
+        assert_eq!(
+            Err(SsssErr::InvalidEncoding { encoding: "bech32m", position: 0 }),
+            Bech32m::new("other").decode(&encoded)
+        );
+        assert_eq!(
+            Err(SsssErr::InvalidEncoding { encoding: "bech32m", position: 5 }),
+            Bech32m::default().decode("ssss1bqqqqqqq")
+        );
+        assert_eq!(
+            Err(SsssErr::InvalidEncoding { encoding: "bech32m", position: 1 }),
+            Bech32m::default().decode("sSss1qqqqqq")
+        );
+    }
+}
@@ -1,3 +1,49 @@
+//! Textual encodings for the data of a shard
+//!
+//! Every encoding implements [`Encoding`], so that [`SsssShard`](crate::SsssShard) can be written
+//! and parsed with any of them. Standard padded base64 is the default.
+
 mod base64;
+mod bech32m;
+mod radix;
+mod words;
+
+pub use self::base64::{base64_encode, base64_decode, base64_decode_lenient};
+pub use self::bech32m::Bech32m;
+pub use self::radix::{Base32, Base64Url, Hex, ZBase32};
+pub(crate) use self::words::lookup;
+pub use self::words::{words_encode, words_decode};
+
+use crate::err::Result;
+
+/// Translates bytes to text and back
+pub trait Encoding {
+    fn encode(&self, data: &[u8]) -> String;
+    fn decode(&self, text: &str) -> Result<Vec<u8>>;
+}
+
+/// Standard base64 with padding, as in RFC 4648
+pub struct Base64;
+
+impl Encoding for Base64 {
+    fn encode(&self, data: &[u8]) -> String {
+        base64_encode(data).expect("every byte-array has a base64 encoding")
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        base64_decode(text)
+    }
+}
+
+/// Words of the SLIP-39 list, see [`words_encode`]
+pub struct Words;
+
+impl Encoding for Words {
+    fn encode(&self, data: &[u8]) -> String {
+        words_encode(data)
+    }
 
-pub use self::base64::{base64_encode, base64_decode};
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        words_decode(text)
+    }
+}
@@ -0,0 +1,170 @@
+//! Encodings that spell a fixed number of bits per character, most significant bit first
+
+use super::Encoding;
+use crate::err::{Result, SsssErr};
+
+/// Spells `x` with `bits` bits per character, padding the last character with zero bits
+fn encode_bits(x: &[u8], bits: usize, alphabet: &[u8]) -> String {
+    let mut output = String::with_capacity((x.len() * 8).div_ceil(bits));
+    let mut accumulator = 0u32;
+    let mut pending = 0;
+
+    for &byte in x {
+        accumulator = (accumulator << 8 | byte as u32) & 0xffff;
+        pending += 8;
+        while pending >= bits {
+            pending -= bits;
+            output.push(alphabet[(accumulator >> pending) as usize & ((1 << bits) - 1)] as char);
+        }
+    }
+    if pending > 0 {
+        output.push(alphabet[(accumulator << (bits - pending)) as usize & ((1 << bits) - 1)] as char);
+    }
+
+    output
+}
+
+/// Inverse of [`encode_bits`]; `value` maps a character to its value, and the padding bits must
+/// be fewer than eight and all zero
+fn decode_bits(x: &str, bits: usize, name: &'static str, value: impl Fn(u8) -> Option<u8>) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(x.len() * bits / 8);
+    let mut accumulator = 0u32;
+    let mut pending = 0;
+
+    for (position, c) in x.bytes().enumerate() {
+        let v = value(c).ok_or(SsssErr::InvalidEncoding { encoding: name, position })?;
+        accumulator = (accumulator << bits | v as u32) & 0xffff;
+        pending += bits;
+        if pending >= 8 {
+            pending -= 8;
+            output.push((accumulator >> pending) as u8);
+        }
+    }
+
+    if pending >= bits || accumulator & ((1 << pending) - 1) != 0 {
+        return Err(SsssErr::InvalidEncoding { encoding: name, position: x.len().saturating_sub(1) });
+    }
+    Ok(output)
+}
+
+fn position_in(alphabet: &[u8], c: u8) -> Option<u8> {
+    alphabet.iter().position(|&a| a == c).map(|p| p as u8)
+}
+
+const HEX_ALPHABET: &[u8] = b"0123456789abcdef";
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const ZBASE32_ALPHABET: &[u8] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+const BASE64URL_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Lowercase hexadecimal, decoded case-insensitively
+pub struct Hex;
+
+impl Encoding for Hex {
+    fn encode(&self, data: &[u8]) -> String {
+        encode_bits(data, 4, HEX_ALPHABET)
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        decode_bits(text, 4, "hex", |c| position_in(HEX_ALPHABET, c.to_ascii_lowercase()))
+    }
+}
+
+/// The padded base32 of RFC 4648, decoded case-insensitively
+pub struct Base32;
+
+impl Encoding for Base32 {
+    fn encode(&self, data: &[u8]) -> String {
+        let mut output = encode_bits(data, 5, BASE32_ALPHABET);
+        let padded = output.len().next_multiple_of(8);
+        output.extend(std::iter::repeat_n('=', padded - output.len()));
+        output
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        let unpadded = text.trim_end_matches('=');
+        if !text.len().is_multiple_of(8) {
+            return Err(SsssErr::InvalidEncoding { encoding: "base32", position: text.len() });
+        }
+        decode_bits(unpadded, 5, "base32", |c| position_in(BASE32_ALPHABET, c.to_ascii_uppercase()))
+    }
+}
+
+/// Phil Zimmermann's human-oriented base32, unpadded, decoded case-insensitively
+pub struct ZBase32;
+
+impl Encoding for ZBase32 {
+    fn encode(&self, data: &[u8]) -> String {
+        encode_bits(data, 5, ZBASE32_ALPHABET)
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        decode_bits(text, 5, "z-base-32", |c| position_in(ZBASE32_ALPHABET, c.to_ascii_lowercase()))
+    }
+}
+
+/// The URL and filename safe base64 of RFC 4648, without padding
+pub struct Base64Url;
+
+impl Encoding for Base64Url {
+    fn encode(&self, data: &[u8]) -> String {
+        encode_bits(data, 6, BASE64URL_ALPHABET)
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        decode_bits(text, 6, "base64url", |c| position_in(BASE64URL_ALPHABET, c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    // The test vectors of RFC 4648
+    #[test_case("", "", "", "" ; "empty")]
+    #[test_case("f", "66", "MY======", "Zg" ; "f")]
+    #[test_case("fo", "666f", "MZXQ====", "Zm8" ; "fo")]
+    #[test_case("foo", "666f6f", "MZXW6===", "Zm9v" ; "foo")]
+    #[test_case("foob", "666f6f62", "MZXW6YQ=", "Zm9vYg" ; "foob")]
+    #[test_case("fooba", "666f6f6261", "MZXW6YTB", "Zm9vYmE" ; "fooba")]
+    #[test_case("foobar", "666f6f626172", "MZXW6YTBOI======", "Zm9vYmFy" ; "foobar")]
+    fn rfc4648(decoded: &str, hex: &str, base32: &str, base64url: &str) {
+        for (encoding, encoded) in [(&Hex as &dyn Encoding, hex), (&Base32, base32), (&Base64Url, base64url)] {
+            assert_eq!(encoded, encoding.encode(decoded.as_bytes()));
+            assert_eq!(decoded.as_bytes(), encoding.decode(encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(b"foobar".to_vec(), Hex.decode("666F6F626172").unwrap());
+        assert_eq!(b"foobar".to_vec(), Base32.decode("mzxw6ytboi======").unwrap());
+        assert_eq!(b"foobar".to_vec(), ZBase32.decode("C3ZS6AUBQE").unwrap());
+    }
+
+    #[test]
+    fn zbase32() {
+        assert_eq!("cf3seamuco", ZBase32.encode(b"asdasd"));
+        assert_eq!(b"asdasd".to_vec(), ZBase32.decode("cf3seamuco").unwrap());
+    }
+
+    #[test]
+    fn url_safe_characters() {
+        assert_eq!("-_8", Base64Url.encode(&[0xfb, 0xff]));
+        assert_eq!(vec![0xfb, 0xff], Base64Url.decode("-_8").unwrap());
+    }
+
+    #[test_case(&Hex, "66z6", 2 ; "hex character")]
+    #[test_case(&Hex, "666", 2 ; "hex odd length")]
+    #[test_case(&Base32, "MZXW6===x", 9 ; "base32 length")]
+    #[test_case(&Base32, "MZ1W6===", 2 ; "base32 character")]
+    #[test_case(&Base32, "MZXR====", 3 ; "base32 trailing bits")]
+    #[test_case(&Base64Url, "Zm9+", 3 ; "base64url plus")]
+    #[test_case(&Base64Url, "Zm9", 2 ; "base64url trailing bits")]
+    fn invalid_input_reports_position(encoding: &dyn Encoding, input: &str, position: usize) {
+        let Err(SsssErr::InvalidEncoding { position: found, .. }) = encoding.decode(input) else {
+            panic!("`{input}` should not decode");
+        };
+        assert_eq!(position, found);
+    }
+}
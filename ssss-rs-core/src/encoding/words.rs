@@ -0,0 +1,164 @@
+//! Spells bytes as words of the SLIP-39 list, ten bits per word, followed by a checksum word
+//!
+//! The checksum word holds the first eight bits of the SHA-256 digest of the data, and the data
+//! length modulo four, which tells apart lengths that need the same number of words. Every word
+//! may be abbreviated to its first four letters, which are unique.
+
+use crate::compat::slip39::words;
+use crate::err::{Result, SsssErr};
+use sha2::{Digest, Sha256};
+
+const RADIX_BITS: usize = 10;
+
+/// Most suggestions offered for a misspelled word
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Largest edit distance at which a word is still suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Translates a byte-array to words
+pub fn words_encode(x: &[u8]) -> String {
+    let bits: Vec<bool> = x.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)).collect();
+
+    bits.chunks(RADIX_BITS)
+        .map(|chunk| {
+            // The last word is padded with zero bits
+            (0..RADIX_BITS).fold(0u16, |acc, i| acc << 1 | chunk.get(i).copied().unwrap_or(false) as u16)
+        })
+        .chain([checksum(x)])
+        .map(|index| words()[index as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Translates words back to the byte-array they spell
+pub fn words_decode(x: &str) -> Result<Vec<u8>> {
+    let indices = x
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| lookup(word, i + 1))
+        .collect::<Result<Vec<u16>>>()?;
+    let Some((&check, indices)) = indices.split_last() else {
+        return Err(SsssErr::MalformedShard("expected at least a checksum word".into()));
+    };
+
+    // A length of 5k + 4 bytes pads the last word with eight bits, and needs as many words as 5k + 5
+    let longest = indices.len() * RADIX_BITS / 8;
+    let len = [longest, longest.saturating_sub(1)]
+        .into_iter()
+        .find(|&len| len % 4 == (check & 0x3) as usize && (len * 8).div_ceil(RADIX_BITS) == indices.len())
+        .ok_or(SsssErr::InvalidChecksum)?;
+
+    let bits: Vec<bool> = indices
+        .iter()
+        .flat_map(|&index| (0..RADIX_BITS).rev().map(move |i| (index >> i) & 1 == 1))
+        .collect();
+    if bits[len * 8..].iter().any(|&bit| bit) {
+        return Err(SsssErr::InvalidChecksum);
+    }
+
+    let data: Vec<u8> = bits[..len * 8]
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, &bit| acc << 1 | bit as u8))
+        .collect();
+    if checksum(&data) != check {
+        return Err(SsssErr::InvalidChecksum);
+    }
+    Ok(data)
+}
+
+fn checksum(x: &[u8]) -> u16 {
+    (Sha256::digest(x)[0] as u16) << 2 | (x.len() % 4) as u16
+}
+
+/// Index of `word` in the list, which may be abbreviated to four letters; `position` only serves
+/// the error, which suggests the nearest words
+pub(crate) fn lookup(word: &str, position: usize) -> Result<u16> {
+    let lower = word.to_lowercase();
+    let list = words();
+
+    let found = match list.binary_search(&lower.as_str()) {
+        Ok(i) => Some(i),
+        Err(i) if lower.len() >= 4 && list.get(i).is_some_and(|w| w.starts_with(&lower)) => Some(i),
+        Err(_) => None,
+    };
+
+    found.map(|i| i as u16).ok_or_else(|| SsssErr::UnknownWord {
+        position,
+        word: word.to_owned(),
+        suggestions: suggestions(&lower),
+    })
+}
+
+fn suggestions(word: &str) -> Vec<String> {
+    let mut candidates: Vec<(usize, &str)> = words()
+        .iter()
+        .map(|&w| (strsim::damerau_levenshtein(word, w), w))
+        .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE)
+        .collect();
+    candidates.sort();
+
+    candidates.into_iter().take(MAX_SUGGESTIONS).map(|(_, w)| w.to_owned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn round_trip_all_lengths() {
+        for len in 0..=40usize {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+            let encoded = words_encode(&data);
+
+            assert_eq!((len * 8).div_ceil(RADIX_BITS) + 1, encoded.split(' ').count());
+            assert_eq!(data, words_decode(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn trailing_zero_bytes() {
+        for data in [vec![0u8; 4], vec![0u8; 5], vec![1, 2, 3, 4, 0], vec![1, 2, 3, 0]] {
+            assert_eq!(data, words_decode(&words_encode(&data)).unwrap());
+        }
+    }
+
+    #[test]
+    fn abbreviations_and_case() {
+        let encoded = words_encode(b"abbreviate");
+        let abbreviated: Vec<String> = encoded.split(' ').map(|w| w[..4.min(w.len())].to_uppercase()).collect();
+
+        assert_eq!(b"abbreviate".to_vec(), words_decode(&abbreviated.join("  \n")).unwrap());
+    }
+
+    #[test]
+    fn swapped_words_fail_checksum() {
+        let encoded = words_encode(b"transcription");
+        let mut split: Vec<&str> = encoded.split(' ').collect();
+        split.swap(1, 2);
+
+        assert_eq!(Err(SsssErr::InvalidChecksum), words_decode(&split.join(" ")));
+    }
+
+    #[test_case("academic acide", "acid" ; "extra letter")]
+    #[test_case("academic aicd", "acid" ; "transposition")]
+    #[test_case("academic acadmic", "academic" ; "missing letter")]
+    fn misspelled_word(input: &str, expected: &str) {
+        let Err(SsssErr::UnknownWord { position, word, suggestions }) = words_decode(input) else {
+            panic!("`{input}` should not decode");
+        };
+
+        assert_eq!(2, position);
+        assert_eq!(input.split(' ').nth(1).unwrap(), word);
+        assert_eq!(expected, suggestions[0]);
+    }
+
+    #[test]
+    fn nothing_close() {
+        assert_eq!(
+            Err(SsssErr::UnknownWord { position: 1, word: "xylophone".into(), suggestions: vec![] }),
+            words_decode("xylophone academic")
+        );
+    }
+}
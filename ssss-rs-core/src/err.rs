@@ -1,10 +1,138 @@
+use crate::math::FieldSize;
+use std::fmt;
+
 pub type Result<T> = std::result::Result<T, SsssErr>;
 
-#[derive(Debug, Clone)]
-pub struct SsssErr;
+/// Everything that can go wrong while sharding, merging or parsing shards
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SsssErr {
+    /// The input is not valid base64; `position` is the offset of the offending character
+    InvalidBase64 { position: usize },
+    /// A shard could not be parsed from its textual representation
+    MalformedShard(String),
+    /// The threshold is zero, or larger than the number of shards
+    InvalidThreshold { threshold: u32, num_shards: u32 },
+    /// There is nothing to share
+    EmptySecret,
+    /// The shard number is zero, which would hold the secret itself, has no element in the field,
+    /// is larger than its set's number of shards, or is not one the reconstructor was built for
+    InvalidShardNumber(u32),
+    /// The same shard number was supplied more than once
+    DuplicateShard(u32),
+    /// The field has fewer non-zero elements than there are shards
+    TooManyShards { field: FieldSize, num_shards: u32 },
+    /// Not all shards carry the same amount of data
+    MismatchedShardLength { expected: usize, found: usize },
+    /// Fewer shards were supplied than are required to reconstruct the secret
+    InsufficientShards { needed: usize, got: usize },
+    /// The shard was written in a format version this library does not understand
+    UnsupportedVersion(u8),
+    /// The shards stem from different splits
+    MixedShardSets,
+    /// The restored secret does not match its digest: shards are corrupt, from different splits, or too few
+    IntegrityCheckFailed,
+    /// Commitments of a verifiable split could not be parsed
+    MalformedCommitments(String),
+    /// These shards do not match the published commitments
+    InvalidShards(Vec<u32>),
+    /// More shards are corrupt than error correction can handle
+    TooManyFaultyShards,
+    /// The operation needs the threshold, but legacy shards do not record it
+    UnknownThreshold,
+    /// A symmetric key must be 32 bytes
+    InvalidKeyLength(usize),
+    /// The payload could not be encrypted
+    EncryptionFailed,
+    /// The ciphertext was tampered with, or the key is wrong
+    DecryptionFailed,
+    /// A stream was required for every shard
+    WrongNumberOfStreams { expected: usize, got: usize },
+    /// Data was required for every shard the reconstructor was built for
+    WrongNumberOfShards { expected: usize, got: usize },
+    /// Reading or writing a stream failed
+    Io(String),
+    /// A classic ssss security level must be a multiple of 8 between 8 and 1024 bits
+    InvalidSecurityLevel(usize),
+    /// The secret does not fit in the field; `max` is the largest length in bytes
+    SecretTooLong { max: usize },
+    /// A classic ssss token is at most 128 characters, none of them a dash
+    InvalidToken(String),
+    /// The checksum of a mnemonic share does not match its words
+    InvalidChecksum,
+    /// A SLIP-39 passphrase may only hold printable ASCII characters
+    InvalidPassphrase,
+    /// A SLIP-39 master secret is an even number of bytes, at least 16
+    InvalidSecretLength(usize),
+    /// A word is not in the word list; `position` counts from 1, `suggestions` are the nearest words
+    UnknownWord { position: usize, word: String, suggestions: Vec<String> },
+    /// The input is not valid in the named encoding; `position` is the offset of the offending character
+    InvalidEncoding { encoding: &'static str, position: usize },
+    /// Zero has no multiplicative inverse in the field
+    DivisionByZero,
+    /// The operation is only implemented for shards over GF(2^8)
+    UnsupportedField(FieldSize),
+}
+
+impl fmt::Display for SsssErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SsssErr::InvalidBase64 { position } => write!(f, "invalid base64 at position {position}"),
+            SsssErr::MalformedShard(reason) => write!(f, "cannot parse shard: {reason}"),
+            SsssErr::InvalidThreshold { threshold, num_shards } => {
+                write!(f, "invalid threshold {threshold} for {num_shards} shards")
+            }
+            SsssErr::EmptySecret => write!(f, "the secret is empty"),
+            SsssErr::InvalidShardNumber(n) => write!(f, "{n} is not a valid shard number"),
+            SsssErr::DuplicateShard(n) => write!(f, "shard {n} was supplied more than once"),
+            SsssErr::TooManyShards { field, num_shards } => {
+                write!(f, "{field} allows at most {} shards, not {num_shards}", field.max_shards())
+            }
+            SsssErr::MismatchedShardLength { expected, found } => {
+                write!(f, "shard holds {found} bytes, expected {expected}")
+            }
+            SsssErr::InsufficientShards { needed, got } => write!(f, "need {needed} shards, have {got}"),
+            SsssErr::UnsupportedVersion(v) => write!(f, "unsupported shard format version {v}"),
+            SsssErr::MixedShardSets => write!(f, "the shards do not all belong to the same split"),
+            SsssErr::IntegrityCheckFailed => write!(f, "integrity check failed"),
+            SsssErr::MalformedCommitments(reason) => write!(f, "cannot parse commitments: {reason}"),
+            SsssErr::InvalidShards(numbers) => write!(f, "shards {numbers:?} do not match the commitments"),
+            SsssErr::TooManyFaultyShards => write!(f, "too many faulty shards to correct"),
+            SsssErr::UnknownThreshold => write!(f, "the shards do not record their threshold"),
+            SsssErr::InvalidKeyLength(len) => write!(f, "invalid key length {len}"),
+            SsssErr::EncryptionFailed => write!(f, "encryption failed"),
+            SsssErr::DecryptionFailed => write!(f, "decryption failed: wrong key or tampered ciphertext"),
+            SsssErr::WrongNumberOfStreams { expected, got } => write!(f, "expected {expected} streams, got {got}"),
+            SsssErr::WrongNumberOfShards { expected, got } => write!(f, "expected {expected} shards, got {got}"),
+            SsssErr::Io(message) => write!(f, "i/o error: {message}"),
+            SsssErr::InvalidSecurityLevel(bits) => write!(f, "invalid security level of {bits} bits"),
+            SsssErr::SecretTooLong { max } => write!(f, "the secret is longer than {max} bytes"),
+            SsssErr::InvalidToken(token) => write!(f, "invalid token `{token}`"),
+            SsssErr::InvalidChecksum => write!(f, "invalid checksum"),
+            SsssErr::InvalidPassphrase => write!(f, "the passphrase may only hold printable ASCII characters"),
+            SsssErr::InvalidSecretLength(len) => write!(f, "invalid secret length {len}"),
+            SsssErr::UnknownWord { position, word, suggestions } => {
+                write!(f, "unknown word `{word}` at position {position}")?;
+                match suggestions.as_slice() {
+                    [] => Ok(()),
+                    [only] => write!(f, ", did you mean `{only}`?"),
+                    [rest @ .., last] => {
+                        let rest: Vec<String> = rest.iter().map(|s| format!("`{s}`")).collect();
+                        write!(f, ", did you mean {} or `{last}`?", rest.join(", "))
+                    }
+                }
+            }
+            SsssErr::InvalidEncoding { encoding, position } => write!(f, "invalid {encoding} at position {position}"),
+            SsssErr::DivisionByZero => write!(f, "division by zero"),
+            SsssErr::UnsupportedField(field) => write!(f, "not supported for shards over {field}"),
+        }
+    }
+}
+
+impl std::error::Error for SsssErr {}
 
-impl std::fmt::Display for SsssErr {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "an error occured in ssss-rs")
+impl From<std::io::Error> for SsssErr {
+    fn from(e: std::io::Error) -> Self {
+        SsssErr::Io(e.to_string())
     }
-}
\ No newline at end of file
+}
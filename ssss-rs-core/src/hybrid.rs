@@ -0,0 +1,109 @@
+//! Hybrid sharing for large payloads
+//!
+//! Rather than sharing the payload itself, which makes every shard as large as the payload, a
+//! random 256-bit key is shared and the payload is encrypted with ChaCha20-Poly1305 under it.
+//! The ciphertext may be stored anywhere: without enough key shards it is useless, and any
+//! tampering with it is detected on decryption.
+
+use crate::err::{Result, SsssErr};
+use crate::{ShamirScheme, SsssShard};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::Rng;
+
+pub const KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 12;
+
+/// Outcome of [`encode`]: one ciphertext for all, and a small key shard per custodian
+#[derive(Debug)]
+pub struct HybridSplit {
+    /// The nonce followed by the encrypted payload and its authentication tag
+    pub ciphertext: Vec<u8>,
+    pub key_shards: Vec<SsssShard>,
+}
+
+/// Encrypts `payload` under a fresh key, and shards that key according to `options`
+pub fn encode(options: &ShamirScheme, payload: &[u8]) -> Result<HybridSplit> {
+    options.validate(payload)?;
+
+    let mut key = [0u8; KEY_LEN];
+    rand::rng().fill_bytes(&mut key);
+
+    let key_shards = crate::encode(options, &key)?;
+    let ciphertext = encrypt(&key, payload)?;
+
+    Ok(HybridSplit { ciphertext, key_shards })
+}
+
+/// Restores the key from `key_shards` and decrypts `ciphertext` with it
+pub fn decode(ciphertext: &[u8], key_shards: &[SsssShard]) -> Result<Vec<u8>> {
+    decrypt(&crate::decode(key_shards)?, ciphertext)
+}
+
+/// Encrypts `payload` under `key` with a random nonce
+pub fn encrypt(key: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key).map_err(|_| SsssErr::InvalidKeyLength(key.len()))?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut nonce);
+
+    let mut ciphertext = nonce.to_vec();
+    ciphertext.extend(
+        cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| SsssErr::EncryptionFailed)?,
+    );
+    Ok(ciphertext)
+}
+
+/// Decrypts and authenticates a ciphertext produced by [`encrypt`]
+pub fn decrypt(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key).map_err(|_| SsssErr::InvalidKeyLength(key.len()))?;
+
+    if ciphertext.len() < NONCE_LEN {
+        return Err(SsssErr::DecryptionFailed);
+    }
+    let (nonce, sealed) = ciphertext.split_at(NONCE_LEN);
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| SsssErr::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_to_end() {
+        let payload = vec![7u8; 10_000];
+        let split = encode(&ShamirScheme::new(3, 5), &payload).unwrap();
+
+        assert_eq!(5, split.key_shards.len());
+        assert!(split.key_shards.iter().all(|s| s.data().len() == KEY_LEN));
+        assert_eq!(payload.len() + NONCE_LEN + 16, split.ciphertext.len());
+        assert_eq!(payload, decode(&split.ciphertext, &split.key_shards[1..4]).unwrap());
+    }
+
+    #[test]
+    fn tampered_ciphertext() {
+        let mut split = encode(&ShamirScheme::new(2, 3), b"database dump").unwrap();
+        split.ciphertext[NONCE_LEN + 1] ^= 1;
+
+        assert_eq!(Err(SsssErr::DecryptionFailed), decode(&split.ciphertext, &split.key_shards));
+    }
+
+    #[test]
+    fn shards_of_another_split() {
+        let split = encode(&ShamirScheme::new(2, 3), b"database dump").unwrap();
+        let other = encode(&ShamirScheme::new(2, 3), b"database dump").unwrap();
+
+        assert_eq!(Err(SsssErr::DecryptionFailed), decode(&split.ciphertext, &other.key_shards));
+    }
+
+    #[test]
+    fn truncated_ciphertext() {
+        assert_eq!(Err(SsssErr::DecryptionFailed), decrypt(&[0; KEY_LEN], &[1, 2, 3]));
+        assert_eq!(Err(SsssErr::InvalidKeyLength(3)), decrypt(&[0; 3], &[0; 40]));
+    }
+}
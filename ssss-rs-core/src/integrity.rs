@@ -0,0 +1,80 @@
+use crate::err::{Result, SsssErr};
+use sha2::{Digest, Sha256};
+
+/// Number of bytes the digest adds to every shard
+pub(crate) const DIGEST_LEN: usize = 32;
+
+/// SHA-256 over the set id and the secret
+///
+/// The digest is appended to the secret before sharing, so it is only revealed once enough shards
+/// are combined, and binding the set id makes shards of another split fail the check.
+pub(crate) fn digest(set_id: u32, secret: &[u8]) -> [u8; DIGEST_LEN] {
+    hasher(set_id).chain_update(secret).finalize().into()
+}
+
+/// Hasher primed with the set id, for digesting a secret piece by piece
+pub(crate) fn hasher(set_id: u32) -> Sha256 {
+    Sha256::new().chain_update(set_id.to_be_bytes())
+}
+
+/// Compares digests without bailing out early, so timing reveals nothing about them
+pub(crate) fn digests_match(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len() && expected.iter().zip(actual).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Appends the digest to `secret`
+pub(crate) fn seal(set_id: u32, secret: &[u8]) -> Vec<u8> {
+    let mut sealed = secret.to_vec();
+    sealed.extend_from_slice(&digest(set_id, secret));
+    sealed
+}
+
+/// Strips and checks the digest appended by [`seal`], returning the bare secret
+pub(crate) fn open(set_id: u32, mut sealed: Vec<u8>) -> Result<Vec<u8>> {
+    if sealed.len() < DIGEST_LEN {
+        return Err(SsssErr::IntegrityCheckFailed);
+    }
+
+    let expected = sealed.split_off(sealed.len() - DIGEST_LEN);
+    if !digests_match(&expected, &digest(set_id, &sealed)) {
+        return Err(SsssErr::IntegrityCheckFailed);
+    }
+
+    Ok(sealed)
+}
+
+/// The CRC-32 of IEEE 802.3, as in zip and PNG; it catches corruption, not tampering
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_open() {
+        let sealed = seal(7, b"secret");
+
+        assert_eq!(6 + DIGEST_LEN, sealed.len());
+        assert_eq!(b"secret".to_vec(), open(7, sealed).unwrap());
+    }
+
+    #[test]
+    fn wrong_set_id() {
+        assert_eq!(Err(SsssErr::IntegrityCheckFailed), open(8, seal(7, b"secret")));
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(Err(SsssErr::IntegrityCheckFailed), open(7, vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(b""));
+    }
+}
@@ -1,105 +1,259 @@
 // Allow dead code and unused imports when testing
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 
-mod encoding;
+pub mod compat;
+pub mod encoding;
 mod err;
+pub mod hybrid;
+mod integrity;
 mod math;
+mod reconstructor;
+mod robust;
+#[cfg(feature = "serde")]
+pub mod serialization;
 mod shard;
-
-use rand::RngExt;
-use math::{GF, GfPoly};
-pub use shard::SsssShard;
-
+pub mod ssms;
+pub mod stream;
+pub mod vss;
+
+use rand::{Rng, RngExt};
+use math::{add_slice, Field, Gf16, Gf32, GF};
+use std::borrow::Cow;
+use std::collections::HashSet;
+pub use err::{Result, SsssErr};
+pub use math::FieldSize;
+pub use reconstructor::Reconstructor;
+pub use robust::{decode_robust, RobustDecoding};
+use shard::ShardSet;
+pub use shard::{SsssShard, BINARY_VERSION, FORMAT_VERSION};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShamirScheme {
-    pub(crate) num_shards: u8,
-    pub(crate) threshold: u8,
+    pub(crate) num_shards: u32,
+    pub(crate) threshold: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) integrity_check: bool,
+    /// `None` for the smallest field with room for all shards
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub(crate) field: Option<FieldSize>,
 }
 
 impl ShamirScheme {
     #[must_use]
-    pub fn new(threshold: u8, num_shards: u8) -> Self {
+    pub fn new(threshold: u32, num_shards: u32) -> Self {
         ShamirScheme {
             threshold,
             num_shards,
+            integrity_check: false,
+            field: None,
+        }
+    }
+
+    /// Computes the split in the given field rather than the smallest one with room for all
+    /// shards
+    #[must_use]
+    pub fn with_field(mut self, field: FieldSize) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    /// The field the split is computed in
+    pub fn field(&self) -> FieldSize {
+        self.field.unwrap_or_else(|| FieldSize::for_shards(self.num_shards))
+    }
+
+    /// Shares a digest of the secret along with it, so `decode` detects wrong or too few shards
+    ///
+    /// This grows every shard by 32 bytes.
+    #[must_use]
+    pub fn with_integrity_check(mut self) -> Self {
+        self.integrity_check = true;
+        self
+    }
+
+    /// Metadata for a fresh split, with a random set id
+    pub(crate) fn new_set(&self) -> ShardSet {
+        ShardSet {
+            id: rand::rng().random(),
+            threshold: self.threshold,
+            num_shards: self.num_shards,
+            checksum: self.integrity_check,
+            field: self.field(),
+        }
+    }
+
+    pub(crate) fn validate(&self, secret: &[u8]) -> Result<()> {
+        if self.threshold == 0 || self.threshold > self.num_shards {
+            return Err(SsssErr::InvalidThreshold { threshold: self.threshold, num_shards: self.num_shards });
+        }
+        if self.num_shards > self.field().max_shards() {
+            return Err(SsssErr::TooManyShards { field: self.field(), num_shards: self.num_shards });
         }
+        if secret.is_empty() {
+            return Err(SsssErr::EmptySecret);
+        }
+        Ok(())
     }
 }
 
-#[must_use]
-pub fn encode(options: &ShamirScheme, secret: &[u8]) -> Vec<SsssShard> {
-    let data_len = secret.len();
-    let rawchunks: Vec<Vec<(u8, u8)>> = (0..data_len)
+/// Splits `secret` into `options.num_shards` shards, any `options.threshold` of which can restore it
+pub fn encode(options: &ShamirScheme, secret: &[u8]) -> Result<Vec<SsssShard>> {
+    options.validate(secret)?;
+
+    let set = options.new_set();
+
+    let mut payload = Cow::Borrowed(secret);
+    if set.checksum {
+        payload = Cow::Owned(integrity::seal(set.id, secret));
+    }
+    if set.field != FieldSize::Gf8 {
+        pad(payload.to_mut(), set.field);
+    }
+
+    Ok(split_bytes(options, &payload)
         .into_iter()
-        .map(|i| {
-            encode_byte(options, secret[i])
-        }).collect();
-
-    (0..rawchunks[0].len())
-        .map(|i| {
-            let data = rawchunks.iter().map(|d| d[i].1).collect::<Vec<_>>();
-            SsssShard::new(options.num_shards, rawchunks[0][i].0, data)
-        }).collect()
+        .zip(1..)
+        .map(|(data, number)| SsssShard::new(set, number, data))
+        .collect())
 }
 
-#[must_use]
-fn encode_byte(options: &ShamirScheme, secret: u8) -> Vec<(u8, u8)> {
-    let mut rng = rand::rng();
-    let mut poly = vec![secret];
-    for _ in 1..options.threshold {
-        poly.push(rng.random());
+/// Pads `secret` to whole elements of a wider field: a `0x80` byte, then zeros
+fn pad(secret: &mut Vec<u8>, field: FieldSize) {
+    secret.push(0x80);
+    secret.resize(secret.len().next_multiple_of(field.element_len()), 0);
+}
+
+/// Strips the padding added by [`pad`]
+fn unpad(mut secret: Vec<u8>) -> Result<Vec<u8>> {
+    let end = secret.iter().rposition(|&b| b != 0);
+    match end {
+        Some(end) if secret[end] == 0x80 => {
+            secret.truncate(end);
+            Ok(secret)
+        }
+        _ => Err(SsssErr::MalformedShard("the restored secret lacks its padding".into())),
     }
+}
 
-    let poly = GfPoly::new(&poly);
+/// Shares every element of `secret`, returning the data of shard 1 up to `num_shards`
+///
+/// Element `i` of every coefficient buffer belongs to the polynomial of secret element `i`, so
+/// each shard is evaluated for all elements at once, by Horner's rule over whole buffers. The
+/// secret must be a whole number of elements of the scheme's field.
+fn split_bytes(options: &ShamirScheme, secret: &[u8]) -> Vec<Vec<u8>> {
+    let mut rng = rand::rng();
+    let coefficients: Vec<Vec<u8>> = (1..options.threshold)
+        .map(|_| {
+            let mut coefficient = vec![0u8; secret.len()];
+            rng.fill_bytes(&mut coefficient);
+            coefficient
+        })
+        .collect();
+
+    // Lowest first, the secret being the constant term; random bytes are random elements
+    let coefficients: Vec<&[u8]> = [secret].into_iter().chain(coefficients.iter().map(Vec::as_slice)).collect();
+    match options.field() {
+        FieldSize::Gf8 => evaluate_shares::<GF>(&coefficients, options.num_shards),
+        FieldSize::Gf16 => evaluate_shares::<Gf16>(&coefficients, options.num_shards),
+        FieldSize::Gf32 => evaluate_shares::<Gf32>(&coefficients, options.num_shards),
+    }
+}
 
-    (1..=options.num_shards)
+/// The data of shard 1 up to `num_shards`, from the coefficient buffers
+fn evaluate_shares<F: Field>(coefficients: &[&[u8]], num_shards: u32) -> Vec<Vec<u8>> {
+    (1..=num_shards)
         .map(|x| {
-            let y = poly.apply_x(x);
-            (x, y.value() as u8)
+            let mut share = vec![0u8; coefficients[0].len()];
+            for_chunks(&mut share, |at, chunk| {
+                let coefficients: Vec<&[u8]> = coefficients.iter().map(|c| &c[at..at + chunk.len()]).collect();
+                evaluate_into(F::from_u32(x), &coefficients, chunk);
+            });
+            share
         })
         .collect()
 }
 
-#[must_use]
-pub fn decode(shards: &[SsssShard]) -> Vec<u8> {
-    let num_bytes = shards[0].data().len();
+/// Writes the polynomials with the given coefficients, evaluated at `x`, into `share`
+fn evaluate_into<F: Field>(x: F, coefficients: &[&[u8]], share: &mut [u8]) {
+    let (highest, lower) = coefficients.split_last().expect("the secret is a coefficient");
+    share.copy_from_slice(highest);
+    for coefficient in lower.iter().rev() {
+        x.mul_slice(share);
+        add_slice(coefficient, share);
+    }
+}
 
-    let mut data = Vec::new();
-    let xvec: Vec<u8> = shards.iter().map(|s| s.num()).collect();
-    let xslice = xvec.as_slice();
+/// Bytes per chunk that [`for_chunks`] hands to a thread; a multiple of every field's element length
+const CHUNK_LEN: usize = 64 * 1024;
 
-    for i in 0..num_bytes {
-        let yvec = shards.iter().map(|s| s.data()[i]).collect::<Vec<_>>();
+/// Calls `f` with the offset of every chunk of `data`: all of it at once, or with the `parallel`
+/// feature, chunks across threads
+#[cfg(not(feature = "parallel"))]
+fn for_chunks(data: &mut [u8], f: impl Fn(usize, &mut [u8]) + Sync) {
+    f(0, data);
+}
 
-        data.push(decode_byte(xslice, yvec.as_slice()));
-    }
+/// Calls `f` with the offset of every chunk of `data`: all of it at once, or with the `parallel`
+/// feature, chunks of [`CHUNK_LEN`] across threads
+#[cfg(feature = "parallel")]
+fn for_chunks(data: &mut [u8], f: impl Fn(usize, &mut [u8]) + Sync) {
+    use rayon::prelude::*;
 
-    data
+    data.par_chunks_mut(CHUNK_LEN).enumerate().for_each(|(i, chunk)| f(i * CHUNK_LEN, chunk));
 }
 
-#[must_use]
-fn decode_byte(x: &[u8], y: &[u8]) -> u8 {
-    assert_eq!(x.len(), y.len());
+/// Restores the secret from a set of shards
+///
+/// The shards are validated up front: at least one must be given, shard numbers must be unique and
+/// non-zero, and all shards must hold the same amount of data. Versioned shards must furthermore
+/// all stem from the same split, and at least `threshold` of them must be supplied.
+///
+/// When the shards carry an integrity check, a secret that does not match its digest is reported
+/// as [`SsssErr::IntegrityCheckFailed`] rather than returned.
+pub fn decode(shards: &[SsssShard]) -> Result<Vec<u8>> {
+    validate_shards(shards)?;
+
+    let x: Vec<u32> = shards.iter().map(|s| s.num()).collect();
+    Reconstructor::for_field(shards[0].field(), &x)?.decode(shards)
+}
 
-    let k = x.len();
-    let mut sum = GF::new(0);
+fn validate_shards(shards: &[SsssShard]) -> Result<()> {
+    let Some(first) = shards.first() else {
+        return Err(SsssErr::InsufficientShards { needed: 1, got: 0 });
+    };
 
-    for j in 0..k {
-        let mut mult = GF::new(y[j]);
-        for m in 0..k {
-            if j == m { continue; }
-            mult *= GF::new(x[m]) / (GF::new(x[m]) - GF::new(x[j]));
+    let set = first.set();
+    if let Some(set) = set
+        && shards.len() < set.threshold as usize
+    {
+        return Err(SsssErr::InsufficientShards { needed: set.threshold as usize, got: shards.len() });
+    }
+
+    let num_bytes = first.data().len();
+    let mut seen = HashSet::new();
+    for s in shards {
+        if s.set() != set {
+            return Err(SsssErr::MixedShardSets);
+        }
+        if s.num() == 0 || s.num() > s.field().max_shards() || set.is_some_and(|set| s.num() > set.num_shards) {
+            return Err(SsssErr::InvalidShardNumber(s.num()));
+        }
+        if !seen.insert(s.num()) {
+            return Err(SsssErr::DuplicateShard(s.num()));
         }
 
-        sum += mult;
+        if s.data().len() != num_bytes {
+            return Err(SsssErr::MismatchedShardLength { expected: num_bytes, found: s.data().len() });
+        }
     }
 
-    sum.value() as u8
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use rand::Rng;
-    use crate::decode_byte;
+    use test_case::test_case;
 
     #[test]
     fn test_end_to_end() {
@@ -108,37 +262,212 @@ mod test {
         let mut secret_bytes = vec![0; 128];
         rng.fill_bytes(&mut secret_bytes);
 
-        let shards = super::encode(&options, &secret_bytes);
+        let shards = super::encode(&options, &secret_bytes).unwrap();
 
-        assert_eq!(secret_bytes, super::decode(&shards[0..3]));
-        assert_eq!(secret_bytes, super::decode(&shards[2..5]));
-        assert_eq!(secret_bytes, super::decode(&shards[3..6]));
-        assert_eq!(secret_bytes, super::decode(&shards[5..8]));
+        assert_eq!(secret_bytes, super::decode(&shards[0..3]).unwrap());
+        assert_eq!(secret_bytes, super::decode(&shards[2..5]).unwrap());
+        assert_eq!(secret_bytes, super::decode(&shards[3..6]).unwrap());
+        assert_eq!(secret_bytes, super::decode(&shards[5..8]).unwrap());
+    }
+
+    #[test_case(4, 3)]
+    #[test_case(0, 3)]
+    #[test_case(0, 0)]
+    #[test_case(1, 0)]
+    fn test_invalid_threshold(threshold: u32, num_shards: u32) {
+        let options = super::ShamirScheme::new(threshold, num_shards);
+
+        assert_eq!(
+            Err(super::SsssErr::InvalidThreshold { threshold, num_shards }),
+            super::encode(&options, &[42]).map(|_| ())
+        );
     }
 
     #[test]
-    fn test_threshold_of_one_is_plain_data() {
-        let options = super::ShamirScheme::new(1, 8);
-        let secret_bytes = [42, 32];
+    fn test_empty_secret() {
+        let options = super::ShamirScheme::new(2, 3);
 
-        let shards = super::encode(&options, &secret_bytes);
+        assert_eq!(Err(super::SsssErr::EmptySecret), super::encode(&options, &[]).map(|_| ()));
+    }
 
-        for s in &shards {
-            assert_eq!([42, 32], s.data());
-        }
+    #[test]
+    fn test_too_many_shards() {
+        let options = super::ShamirScheme::new(2, 300).with_field(super::FieldSize::Gf8);
+
+        assert_eq!(
+            Err(super::SsssErr::TooManyShards { field: super::FieldSize::Gf8, num_shards: 300 }),
+            super::encode(&options, &[42]).map(|_| ())
+        );
+    }
+
+    #[test_case(b"x"; "one byte")]
+    #[test_case(b"odd length"; "even length")]
+    #[test_case(b"an odd length secret"; "odd length")]
+    #[test_case(&[0x80, 0, 0]; "padding-like ending")]
+    fn test_more_than_255_shards(secret: &[u8]) {
+        let options = super::ShamirScheme::new(3, 300);
+        let mut shards = super::encode(&options, secret).unwrap();
+
+        assert_eq!(super::FieldSize::Gf16, shards[0].field());
+        assert_eq!(300, shards[299].number());
+        assert_eq!(secret, super::decode(&shards[297..]).unwrap());
+
+        shards.truncate(3);
+        shards.swap(0, 2);
+        assert_eq!(secret, super::decode(&shards).unwrap());
     }
 
     #[test]
-    fn test_single_byte() {
-        let secret_byte = 42u8;
-        let options = super::ShamirScheme::new(2, 2);
+    fn test_wide_field_with_integrity_check() {
+        let options = super::ShamirScheme::new(2, 3).with_field(super::FieldSize::Gf32).with_integrity_check();
+        let shards = super::encode(&options, b"forced").unwrap();
+        let text: Vec<String> = shards.iter().map(|s| s.to_string()).collect();
+
+        assert!(text[0].contains("-05-2-3-1-"));
+        assert_eq!(b"forced".to_vec(), super::decode(&parse_all(&text[1..])).unwrap());
+
+        let mut tampered = parse_all(&text[..2]);
+        tampered[0] = super::SsssShard::new(tampered[0].set().unwrap(), 1, vec![0; tampered[0].data().len()]);
+        assert_eq!(Err(super::SsssErr::IntegrityCheckFailed), super::decode(&tampered));
+    }
 
-        let encoded_bytes = super::encode_byte(&options, secret_byte);
+    #[test]
+    fn test_no_shards() {
+        assert_eq!(Err(super::SsssErr::InsufficientShards { needed: 1, got: 0 }), super::decode(&[]));
+    }
+
+    #[test]
+    fn test_duplicate_shard() {
+        let options = super::ShamirScheme::new(2, 3);
+        let shards = super::encode(&options, &[1, 2, 3]).unwrap();
+        let duplicate = shards[1].to_string().parse().unwrap();
+        let shards = [shards.into_iter().nth(1).unwrap(), duplicate];
+
+        assert_eq!(Err(super::SsssErr::DuplicateShard(2)), super::decode(&shards));
+    }
+
+    #[test]
+    fn test_too_few_shards() {
+        let options = super::ShamirScheme::new(3, 5);
+        let shards = super::encode(&options, &[1, 2, 3]).unwrap();
+
+        assert_eq!(
+            Err(super::SsssErr::InsufficientShards { needed: 3, got: 2 }),
+            super::decode(&shards[1..3])
+        );
+    }
+
+    #[test]
+    fn test_mixed_shard_sets() {
+        let options = super::ShamirScheme::new(2, 3);
+        let mut shards = super::encode(&options, &[1, 2, 3]).unwrap();
+        let other = super::encode(&options, &[1, 2, 3]).unwrap();
+        shards[1] = other.into_iter().nth(1).unwrap();
+
+        assert_eq!(Err(super::SsssErr::MixedShardSets), super::decode(&shards));
+    }
+
+    #[test]
+    fn test_mixed_legacy_and_versioned() {
+        let options = super::ShamirScheme::new(2, 3);
+        let mut shards = super::encode(&options, &[1, 2, 3]).unwrap();
+        shards[1] = "2-AQID".parse().unwrap();
 
-        println!("{:?}", encoded_bytes);
+        assert_eq!(Err(super::SsssErr::MixedShardSets), super::decode(&shards));
+    }
+
+    #[test]
+    fn test_legacy_shards() {
+        // "Hi" shared with the polynomial `secret + x`
+        let shards: Vec<super::SsssShard> = vec!["1-SWg=".parse().unwrap(), "2-Sms=".parse().unwrap()];
+
+        assert_eq!(b"Hi".to_vec(), super::decode(&shards).unwrap());
+
+        assert!(matches!("300-Sms=".parse::<super::SsssShard>(), Err(super::SsssErr::MalformedShard(_))));
+    }
+
+    fn checked_shards(threshold: u32, num_shards: u32, secret: &[u8]) -> Vec<String> {
+        let options = super::ShamirScheme::new(threshold, num_shards).with_integrity_check();
+        super::encode(&options, secret).unwrap().iter().map(|s| s.to_string()).collect()
+    }
 
-        let decoded_poly = decode_byte(&[1, 2], &[encoded_bytes[0].1, encoded_bytes[1].1]);
-        assert_eq!(42, decoded_poly);
+    fn parse_all(shards: &[String]) -> Vec<super::SsssShard> {
+        shards.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_integrity_check_round_trip() {
+        let shards = checked_shards(3, 5, b"attack at dawn");
+
+        assert_eq!(b"attack at dawn".to_vec(), super::decode(&parse_all(&shards[1..4])).unwrap());
+        assert_eq!(b"attack at dawn".to_vec(), super::decode(&parse_all(&shards)).unwrap());
+    }
+
+    #[test]
+    fn test_integrity_check_under_threshold() {
+        // Claim a lower threshold than was used to create the shards
+        let shards: Vec<String> = checked_shards(3, 5, b"attack at dawn")
+            .iter()
+            .map(|s| s.replacen("-3-5-", "-2-5-", 1))
+            .collect();
+
+        assert_eq!(Err(super::SsssErr::IntegrityCheckFailed), super::decode(&parse_all(&shards[0..2])));
+    }
+
+    #[test]
+    fn test_integrity_check_tampered() {
+        let mut shards = checked_shards(2, 3, b"attack at dawn");
+        // Flip a character of the payload of the second shard
+        let position = shards[1].rfind('-').unwrap() + 2;
+        let flipped = if &shards[1][position..=position] == "A" { "B" } else { "A" };
+        shards[1].replace_range(position..=position, flipped);
+        let shards = parse_all(&shards[0..2]);
+
+        assert_eq!(Err(super::SsssErr::IntegrityCheckFailed), super::decode(&shards));
+    }
+
+    #[test]
+    fn test_integrity_check_mixed_sets() {
+        let first = checked_shards(2, 3, b"attack at dawn");
+        let second = checked_shards(2, 3, b"attack at dusk");
+        // Pretend the second split has the set id of the first
+        let id = &first[0][3..11];
+        let forged = format!("{}{}{}", &second[1][..3], id, &second[1][11..]);
+
+        assert_eq!(
+            Err(super::SsssErr::IntegrityCheckFailed),
+            super::decode(&parse_all(&[first[0].clone(), forged]))
+        );
+    }
+
+    #[test]
+    fn test_shard_number_zero() {
+        let shards: Vec<super::SsssShard> = vec!["0-AQID".parse().unwrap(), "1-AQID".parse().unwrap()];
+
+        assert_eq!(Err(super::SsssErr::InvalidShardNumber(0)), super::decode(&shards));
+    }
+
+    #[test]
+    fn test_mismatched_shard_length() {
+        let mut shards = vec!["1-AQID".parse().unwrap(), "2-AQID".parse().unwrap()];
+        shards[1] = "2-AQI=".parse().unwrap();
+
+        assert_eq!(
+            Err(super::SsssErr::MismatchedShardLength { expected: 3, found: 2 }),
+            super::decode(&shards)
+        );
+    }
+
+    #[test]
+    fn test_threshold_of_one_is_plain_data() {
+        let options = super::ShamirScheme::new(1, 8);
+        let secret_bytes = [42, 32];
+
+        let shards = super::encode(&options, &secret_bytes).unwrap();
+
+        for s in &shards {
+            assert_eq!([42, 32], s.data());
+        }
     }
 
     #[test]
@@ -148,7 +477,7 @@ mod test {
         let secret = "a".repeat(100);
         let secret_bytes = secret.as_bytes();
 
-        let shards = super::encode(&options, &secret_bytes);
+        let shards = super::encode(&options, secret_bytes).unwrap();
 
         for s in &shards {
             println!("{s}");
@@ -162,4 +491,23 @@ mod test {
         assert_ne!(&secret_bytes[50..], &shards[1].data()[50..]);
         assert_ne!(&shards[0].data()[50..], &shards[1].data()[50..]);
     }
+
+    #[test]
+    fn test_chunks_match_whole_buffer() {
+        // Several chunks of the `parallel` feature, and a partial one
+        let mut coefficients = vec![vec![0u8; 200_000]; 3];
+        coefficients.iter_mut().for_each(|c| rand::rng().fill_bytes(c));
+        let coefficients: Vec<&[u8]> = coefficients.iter().map(Vec::as_slice).collect();
+
+        let shares = super::evaluate_shares::<super::GF>(&coefficients, 4);
+        for (x, share) in (1..=4).zip(&shares) {
+            let mut whole = vec![0u8; 200_000];
+            super::evaluate_into(super::GF::new(x), &coefficients, &mut whole);
+            assert_eq!(&whole, share);
+        }
+
+        let set = super::ShamirScheme::new(3, 4).new_set();
+        let shards: Vec<_> = shares.into_iter().enumerate().map(|(i, data)| super::SsssShard::new(set, i as u32 + 1, data)).collect();
+        assert_eq!(coefficients[0], super::decode(&shards[1..]).unwrap());
+    }
 }
@@ -0,0 +1,328 @@
+//! The binary fields a split can be computed in
+//!
+//! Every shard number is a non-zero element of the field, so GF(2^8) allows 255 shards. The wider
+//! fields allow more, at the cost of padding the secret to a whole number of elements. Like
+//! [`GF`], their arithmetic runs in constant time.
+
+use crate::err::{Result, SsssErr};
+use crate::math::{mul_add_slice, mul_slice, GF};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub};
+
+/// Which field a split is computed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum FieldSize {
+    /// GF(2^8), a byte per element and at most 255 shards
+    #[default]
+    Gf8,
+    /// GF(2^16), at most 65535 shards
+    Gf16,
+    /// GF(2^32), at most 4294967295 shards
+    Gf32,
+}
+
+impl FieldSize {
+    /// The smallest field with enough elements for `num_shards` shards
+    pub fn for_shards(num_shards: u32) -> FieldSize {
+        [FieldSize::Gf8, FieldSize::Gf16]
+            .into_iter()
+            .find(|f| num_shards <= f.max_shards())
+            .unwrap_or(FieldSize::Gf32)
+    }
+
+    pub const fn bits(self) -> u32 {
+        match self {
+            FieldSize::Gf8 => 8,
+            FieldSize::Gf16 => 16,
+            FieldSize::Gf32 => 32,
+        }
+    }
+
+    /// Bytes per element, the secret is padded to a multiple of this
+    pub fn element_len(self) -> usize {
+        self.bits() as usize / 8
+    }
+
+    /// The largest shard number, as zero is never handed out
+    pub const fn max_shards(self) -> u32 {
+        (u64::pow(2, self.bits()) - 1) as u32
+    }
+}
+
+impl fmt::Display for FieldSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GF(2^{})", self.bits())
+    }
+}
+
+/// Arithmetic that sharing and reconstruction need from a field
+pub trait Field:
+    Copy + PartialEq + fmt::Debug + Send + Sync
+    + Add<Output = Self> + AddAssign + Sub<Output = Self> + Mul<Output = Self> + MulAssign
+{
+    const SIZE: FieldSize;
+
+    /// The element with the given value; panics if it is outside the field
+    fn from_u32(value: u32) -> Self;
+    fn to_u32(self) -> u32;
+    fn invert(self) -> Result<Self>;
+
+    /// Reads an element from its big-endian bytes
+    fn read(bytes: &[u8]) -> Self {
+        Self::from_u32(bytes.iter().fold(0, |value, &b| value << 8 | b as u32))
+    }
+
+    /// Writes the element as big-endian bytes
+    fn write(self, bytes: &mut [u8]) {
+        let value = self.to_u32().to_be_bytes();
+        bytes.copy_from_slice(&value[4 - bytes.len()..]);
+    }
+
+    /// Multiplies every element of `data` by `self`
+    fn mul_slice(self, data: &mut [u8]) {
+        for element in data.chunks_exact_mut(Self::SIZE.element_len()) {
+            (self * Self::read(element)).write(element);
+        }
+    }
+
+    /// Adds `self` times every element of `src` to the element of `dst` at the same offset
+    fn mul_add_slice(self, src: &[u8], dst: &mut [u8]) {
+        let len = Self::SIZE.element_len();
+        for (s, d) in src.chunks_exact(len).zip(dst.chunks_exact_mut(len)) {
+            (Self::read(d) + self * Self::read(s)).write(d);
+        }
+    }
+}
+
+impl Field for GF {
+    const SIZE: FieldSize = FieldSize::Gf8;
+
+    fn from_u32(value: u32) -> GF {
+        GF::new(u8::try_from(value).expect("element of GF(2^8)"))
+    }
+
+    fn to_u32(self) -> u32 {
+        self.value() as u32
+    }
+
+    fn invert(self) -> Result<GF> {
+        GF::invert(self)
+    }
+
+    fn mul_slice(self, data: &mut [u8]) {
+        mul_slice(self, data);
+    }
+
+    fn mul_add_slice(self, src: &[u8], dst: &mut [u8]) {
+        mul_add_slice(self, src, dst);
+    }
+}
+
+/// All ones if bit `i` of `n` is set, else all zeros
+fn bit_mask(n: u64, i: u32) -> u64 {
+    ((n >> i) & 1).wrapping_neg()
+}
+
+macro_rules! binary_field {
+    ($(#[$doc:meta])* $name:ident, $size:expr, $poly:expr) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+        pub struct $name(u64);
+
+        impl $name {
+            const K: u32 = $size.bits();
+            const Q: u64 = $poly;
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            #[allow(clippy::suspicious_arithmetic_impl)]
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 ^ rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            #[allow(clippy::suspicious_op_assign_impl)]
+            fn add_assign(&mut self, rhs: $name) {
+                self.0 ^= rhs.0;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            #[allow(clippy::suspicious_arithmetic_impl)]
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 ^ rhs.0)
+            }
+        }
+
+        impl Mul for $name {
+            type Output = $name;
+            fn mul(self, rhs: $name) -> $name {
+                // Shift and add like GF, masking rather than branching on the bits
+                let (mut a, mut c) = (self.0, 0);
+                for i in 0..Self::K {
+                    c ^= a & bit_mask(rhs.0, i);
+                    a <<= 1;
+                    a ^= Self::Q & bit_mask(a, Self::K);
+                }
+                $name(c)
+            }
+        }
+
+        impl MulAssign for $name {
+            fn mul_assign(&mut self, rhs: $name) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl Field for $name {
+            const SIZE: FieldSize = $size;
+
+            fn from_u32(value: u32) -> $name {
+                assert!(value <= $size.max_shards(), "element of {}", $size);
+                $name(value as u64)
+            }
+
+            fn to_u32(self) -> u32 {
+                self.0 as u32
+            }
+
+            /// `self^(2^K - 2)`, by a fixed chain of squarings and multiplications
+            fn invert(self) -> Result<$name> {
+                if self.0 == 0 {
+                    return Err(SsssErr::DivisionByZero);
+                }
+
+                let (mut square, mut inverse) = (self, $name(1));
+                for _ in 1..Self::K {
+                    square *= square;
+                    inverse *= square;
+                }
+                Ok(inverse)
+            }
+        }
+    };
+}
+
+binary_field!(
+    /// GF(2^16), reducing by `x^16 + x^12 + x^3 + x + 1`
+    Gf16, FieldSize::Gf16, 0x1_100b
+);
+binary_field!(
+    /// GF(2^32), reducing by the primitive `x^32 + x^22 + x^2 + x + 1`
+    Gf32, FieldSize::Gf32, 0x1_0040_0007
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngExt;
+    use test_case::test_case;
+
+    #[test_case(1, FieldSize::Gf8)]
+    #[test_case(255, FieldSize::Gf8)]
+    #[test_case(256, FieldSize::Gf16)]
+    #[test_case(65535, FieldSize::Gf16)]
+    #[test_case(65536, FieldSize::Gf32)]
+    #[test_case(u32::MAX, FieldSize::Gf32)]
+    fn smallest_field(num_shards: u32, expected: FieldSize) {
+        assert_eq!(expected, FieldSize::for_shards(num_shards));
+    }
+
+    /// Schoolbook carry-less multiplication, reduced afterwards
+    fn reference_mul(a: u64, b: u64, k: u32, q: u64) -> u64 {
+        let mut c = 0;
+        for i in 0..k {
+            if (b >> i) & 1 == 1 {
+                c ^= a << i;
+            }
+        }
+        for i in (k..2 * k).rev() {
+            if (c >> i) & 1 == 1 {
+                c ^= q << (i - k);
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn mul_matches_reference() {
+        let mut rng = rand::rng();
+        for _ in 0..10_000 {
+            let (a, b): (u16, u16) = (rng.random(), rng.random());
+            let product = Gf16::from_u32(a as u32) * Gf16::from_u32(b as u32);
+            assert_eq!(reference_mul(a as u64, b as u64, 16, Gf16::Q), product.0);
+
+            let (a, b): (u32, u32) = (rng.random(), rng.random());
+            let product = Gf32::from_u32(a) * Gf32::from_u32(b);
+            assert_eq!(reference_mul(a as u64, b as u64, 32, Gf32::Q), product.0);
+        }
+    }
+
+    #[test]
+    fn every_gf16_element_has_an_inverse() {
+        // Which also shows the polynomial is irreducible
+        for a in 1..=u16::MAX as u32 {
+            let a = Gf16::from_u32(a);
+            assert_eq!(Gf16(1), a * a.invert().unwrap());
+        }
+        assert_eq!(Err(SsssErr::DivisionByZero), Gf16(0).invert());
+    }
+
+    fn pow(a: Gf32, mut exponent: u64) -> Gf32 {
+        let (mut base, mut result) = (a, Gf32(1));
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result *= base;
+            }
+            base *= base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    #[test]
+    fn gf32_polynomial_is_primitive() {
+        // x generates all 2^32 - 1 non-zero elements: its order divides none of the maximal
+        // divisors, for the prime factors 3, 5, 17, 257 and 65537
+        let order = u32::MAX as u64;
+        assert_eq!(Gf32(1), pow(Gf32(2), order));
+        for p in [3, 5, 17, 257, 65537] {
+            assert_ne!(Gf32(1), pow(Gf32(2), order / p));
+        }
+
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let a = Gf32::from_u32(rng.random_range(1..=u32::MAX));
+            assert_eq!(Gf32(1), a * a.invert().unwrap());
+        }
+    }
+
+    #[test]
+    fn slices_match_elements() {
+        let (a, b) = (Gf16::from_u32(0x1234), Gf16::from_u32(0xfedc));
+        let mut data = [0x12, 0x34, 0xfe, 0xdc];
+        a.mul_slice(&mut data);
+        assert_eq!((a * a, a * b), (Gf16::read(&data[..2]), Gf16::read(&data[2..])));
+
+        let mut sum = [0x12, 0x34, 0xfe, 0xdc, 0x00, 0x01];
+        a.mul_add_slice(&[0xfe, 0xdc, 0x12, 0x34], &mut sum);
+        assert_eq!((a + a * b, b + a * a), (Gf16::read(&sum[..2]), Gf16::read(&sum[2..4])));
+        assert_eq!([0x00, 0x01], sum[4..]);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut bytes = [0u8; 4];
+        Gf32::from_u32(0x1234_5678).write(&mut bytes);
+        assert_eq!([0x12, 0x34, 0x56, 0x78], bytes);
+        assert_eq!(Gf32::from_u32(0x1234_5678), Gf32::read(&bytes));
+
+        Gf16::from_u32(0xbeef).write(&mut bytes[..2]);
+        assert_eq!(Gf16::from_u32(0xbeef), Gf16::read(&bytes[..2]));
+        assert_eq!(GF::new(0xbe), GF::read(&bytes[..1]));
+    }
+}
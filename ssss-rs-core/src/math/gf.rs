@@ -1,12 +1,18 @@
+//! GF(2^8) with the AES polynomial
+//!
+//! Shard and secret bytes flow through this arithmetic, so it runs in constant time: no branch
+//! and no memory access depends on the value of an element. Only whether an element is zero shows,
+//! when inverting it fails.
+
 use std::convert::TryInto;
-use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub};
+use crate::err::{Result, SsssErr};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct GF(u64);
 
 pub const K: usize = 8;
 pub const Q: u64 = 283;
-static INVERSE_LUT: std::sync::OnceLock<Vec<GF>> = std::sync::OnceLock::new();
 
 impl GF {
     pub fn new(val: u8) -> GF {
@@ -25,51 +31,32 @@ impl GF {
         self.0
     }
 
-    fn get_inverse_lut() -> &'static [GF] {
-        INVERSE_LUT.get_or_init(|| {
-            // Build up the inverse table using brute force
-            let mut lut = vec![GF(0); GF::number_of_elements()];
-
-            // Find the inverse for each of the numbers {1, 2, ..., N-1}
-            for x in 1..GF::number_of_elements() {
-                // Scan the numbers {1, 2, ..., N-1} until we find the inverse
-                let x = GF(x as u64);
-                let mut found = false;
-                for y in 1..GF::number_of_elements() {
-                    let y = GF(y as u64);
-                    if x * y == GF(1) {
-                        lut[x.0 as usize] = y;
-                        found = true;
-                        break;
-                    }
-                }
-                if !found {
-                    unreachable!("Every non-zero number has an inverse");
-                }
-            }
-
-            lut
-        })
-    }
-
-    fn invert(self) -> Result<GF, String> {
+    /// The multiplicative inverse, `self^254`, by a fixed chain of squarings and multiplications
+    pub fn invert(self) -> Result<GF> {
         // Important: Zero has no inverse, it's invalid
         if self.0 == 0 {
-            return Err("Zero has no inverse".to_string());
+            return Err(SsssErr::DivisionByZero);
+        }
+
+        // 254 = 0b11111110: square and multiply through the seven ones, then square once more
+        let mut power = self;
+        for _ in 0..6 {
+            power = power * power * self;
         }
-        // Perform a lookup in the pre-computed table
-        Ok(GF::get_inverse_lut()[self.0 as usize])
+        Ok(power * power)
     }
 }
 
 impl Add<GF> for GF {
     type Output = GF;
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn add(self, rhs: GF) -> GF {
         GF(self.0 ^ rhs.0)
     }
 }
 
 impl AddAssign<GF> for GF {
+    #[allow(clippy::suspicious_op_assign_impl)]
     fn add_assign(&mut self, rhs: GF) {
         self.0 ^= rhs.0;
     }
@@ -84,13 +71,22 @@ impl Neg for GF {
 
 impl Sub<GF> for GF {
     type Output = GF;
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn sub(self, rhs: GF) -> GF {
         GF(self.0 ^ rhs.0)
     }
 }
 
-fn extract_bit(n: u64, i: usize) -> u64 {
-    (n >> i) & 1
+impl SubAssign<GF> for GF {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn sub_assign(&mut self, rhs: GF) {
+        self.0 ^= rhs.0;
+    }
+}
+
+/// All ones if bit `i` of `n` is set, else all zeros
+fn bit_mask(n: u64, i: usize) -> u64 {
+    ((n >> i) & 1).wrapping_neg()
 }
 
 impl Mul<GF> for GF {
@@ -102,15 +98,11 @@ impl Mul<GF> for GF {
         let b: u64 = rhs.0;
         let mut c: u64 = 0;
 
-        // Loop over each possible term
+        // Loop over each possible term, masking rather than branching on the bits
         for i in 0..K {
-            if extract_bit(b, i) == 1 {
-                c ^= a; // c = poly_add(c, a)
-            }
+            c ^= a & bit_mask(b, i); // c = poly_add(c, a) if bit i of b is set
             a <<= 1;
-            if extract_bit(a, K) == 1 {
-                a ^= Q; // a = poly_sub(a, Q)
-            }
+            a ^= Q & bit_mask(a, K); // a = poly_sub(a, Q) if a overflowed
         }
         GF(c)
     }
@@ -122,17 +114,6 @@ impl MulAssign<GF> for GF {
     }
 }
 
-impl Div<GF> for GF {
-    type Output = GF;
-    fn div(self, rhs: Self) -> GF {
-        // Important: Cannot divide by zero
-        if rhs.0 == 0 {
-            panic!("Cannot divide by zero");
-        }
-        self * rhs.invert().unwrap()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +133,111 @@ mod tests {
         assert_eq!(GF::new(0), GF::add(GF::new(3), GF::new(3)));
         assert_eq!(GF::new(0), GF::add(GF::new(255), GF::new(255)));
     }
+
+    #[test]
+    fn invert() {
+        assert_eq!(Err(SsssErr::DivisionByZero), GF::new(0).invert());
+        for x in 1..=255 {
+            assert_eq!(GF::new(1), GF::new(x) * GF::new(x).invert().unwrap());
+        }
+        // The AES S-box pair
+        assert_eq!(GF::new(0xca), GF::new(0x53).invert().unwrap());
+    }
+
+    /// Schoolbook carry-less multiplication, reduced afterwards
+    fn reference_mul(a: u64, b: u64) -> u64 {
+        let mut c = 0;
+        for i in 0..K {
+            if (b >> i) & 1 == 1 {
+                c ^= a << i;
+            }
+        }
+        for i in (K..2 * K).rev() {
+            if (c >> i) & 1 == 1 {
+                c ^= Q << (i - K);
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn mul_matches_reference() {
+        for a in 0..=255 {
+            for b in 0..=255 {
+                assert_eq!(reference_mul(a, b), (GF::new(a as u8) * GF::new(b as u8)).value());
+            }
+        }
+    }
+
+    /// Welch's t-statistic between two samples of timings
+    fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+        let mean_var = |s: &[f64]| {
+            let mean = s.iter().sum::<f64>() / s.len() as f64;
+            let var = s.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (s.len() - 1) as f64;
+            (mean, var)
+        };
+        let ((ma, va), (mb, vb)) = (mean_var(a), mean_var(b));
+        (ma - mb) / (va / a.len() as f64 + vb / b.len() as f64).sqrt()
+    }
+
+    /// Times `op` on a fixed input against random inputs, dudect style, and returns |t|
+    fn leakage(op: impl Fn(u8) -> u64) -> f64 {
+        use rand::{Rng, RngExt};
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        const BATCH: usize = 256;
+        const WARM_UP: usize = 2_000;
+        const ROUNDS: usize = WARM_UP + 20_000;
+
+        // Prepare every input up front, so both classes run the same code between measurements
+        let mut rng = rand::rng();
+        let classes: Vec<bool> = (0..ROUNDS).map(|_| rng.random_bool(0.5)).collect();
+        let mut inputs = vec![1u8; ROUNDS * BATCH];
+        for (batch, &is_fixed) in inputs.chunks_mut(BATCH).zip(&classes) {
+            if !is_fixed {
+                rng.fill_bytes(batch);
+                batch.iter_mut().for_each(|x| *x |= 1); // keep zero out, its failure may be fast
+            }
+        }
+
+        let (mut fixed, mut random) = (Vec::new(), Vec::new());
+        for (round, (batch, &is_fixed)) in inputs.chunks(BATCH).zip(&classes).enumerate() {
+            let start = Instant::now();
+            for &x in batch {
+                black_box(op(black_box(x)));
+            }
+            let elapsed = start.elapsed().as_nanos() as f64;
+            if round < WARM_UP {
+                continue;
+            }
+            if is_fixed { fixed.push(elapsed) } else { random.push(elapsed) }
+        }
+
+        // Crop the slowest tenth of each class, which is mostly interrupts and scheduling
+        for samples in [&mut fixed, &mut random] {
+            samples.sort_by(f64::total_cmp);
+            samples.truncate(samples.len() * 9 / 10);
+        }
+        welch_t(&fixed, &random).abs()
+    }
+
+    /// A statistical check that timing doesn't depend on the operands; noisy in debug builds, run
+    /// with `cargo test --release -- --ignored constant_time`
+    #[test]
+    #[ignore]
+    fn constant_time() {
+        // dudect's threshold for "definitely not constant time"
+        const THRESHOLD: f64 = 10.0;
+
+        let secret = GF::new(0xa7);
+        let mul = leakage(|x| (GF::new(x) * secret).value());
+        let invert = leakage(|x| GF::new(x).invert().unwrap().value());
+        let reconstructor = crate::Reconstructor::new(&[1, 2, 3]).unwrap();
+        let interpolate = leakage(|x| reconstructor.reconstruct(&[[x], [x ^ 0x5c], [0x3e]]).unwrap()[0] as u64);
+
+        assert!(mul < THRESHOLD, "mul leaks: t = {mul}");
+        assert!(invert < THRESHOLD, "invert leaks: t = {invert}");
+        assert!(interpolate < THRESHOLD, "interpolation leaks: t = {interpolate}");
+    }
 }
\ No newline at end of file
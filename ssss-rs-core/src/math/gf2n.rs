@@ -0,0 +1,167 @@
+use crate::err::{Result, SsssErr};
+
+/// Element of GF(2^n) for a degree n of at most 1024, as little-endian 64-bit limbs
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Gf2nElement(Vec<u64>);
+
+/// The binary field GF(2^n), reducing by a pentanomial `x^n + x^a + x^b + x^c + 1`
+pub struct Gf2n {
+    degree: usize,
+    poly: Gf2nElement,
+}
+
+impl Gf2nElement {
+    /// All ones if bit `i` is set, else all zeros
+    fn bit_mask(&self, i: usize) -> u64 {
+        ((self.0[i / 64] >> (i % 64)) & 1).wrapping_neg()
+    }
+
+    fn set_bit(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn xor_assign(&mut self, rhs: &Gf2nElement) {
+        self.masked_xor_assign(rhs, u64::MAX);
+    }
+
+    /// Adds `rhs` where `mask` is set, without branching on it
+    fn masked_xor_assign(&mut self, rhs: &Gf2nElement, mask: u64) {
+        for (a, b) in self.0.iter_mut().zip(&rhs.0) {
+            *a ^= b & mask;
+        }
+    }
+
+    fn shift_left_one(&mut self) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut() {
+            let next = *limb >> 63;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|&l| l == 0)
+    }
+}
+
+impl Gf2n {
+    /// The field of the given degree; `taps` are the exponents `a`, `b` and `c` of the reduction
+    /// pentanomial
+    pub fn new(degree: usize, taps: [usize; 3]) -> Gf2n {
+        let mut field = Gf2n { degree, poly: Gf2nElement(vec![]) };
+        let mut poly = field.zero();
+        for bit in [degree, taps[0], taps[1], taps[2], 0] {
+            poly.set_bit(bit);
+        }
+        field.poly = poly;
+        field
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    pub fn zero(&self) -> Gf2nElement {
+        // One extra limb, so the element may briefly hold bit `degree` during reduction
+        Gf2nElement(vec![0; self.degree / 64 + 1])
+    }
+
+    pub fn element(&self, value: u64) -> Gf2nElement {
+        let mut element = self.zero();
+        element.0[0] = value;
+        element
+    }
+
+    /// Interprets big-endian bytes as the coefficients of an element, highest first
+    pub fn import(&self, bytes: &[u8]) -> Result<Gf2nElement> {
+        if bytes.len() * 8 > self.degree {
+            return Err(SsssErr::SecretTooLong { max: self.degree / 8 });
+        }
+
+        let mut element = self.zero();
+        for (i, &byte) in bytes.iter().rev().enumerate() {
+            element.0[i / 8] |= (byte as u64) << (8 * (i % 8));
+        }
+        Ok(element)
+    }
+
+    /// Big-endian bytes of the element, `degree / 8` of them
+    pub fn export(&self, element: &Gf2nElement) -> Vec<u8> {
+        (0..self.degree / 8)
+            .rev()
+            .map(|i| (element.0[i / 8] >> (8 * (i % 8))) as u8)
+            .collect()
+    }
+
+    pub fn add(&self, a: &Gf2nElement, b: &Gf2nElement) -> Gf2nElement {
+        let mut sum = a.clone();
+        sum.xor_assign(b);
+        sum
+    }
+
+    pub fn mul(&self, a: &Gf2nElement, b: &Gf2nElement) -> Gf2nElement {
+        let mut shifted = a.clone();
+        let mut product = self.zero();
+
+        // Masks rather than branches on the bits, so the timing doesn't depend on secret values
+        for i in 0..self.degree {
+            product.masked_xor_assign(&shifted, b.bit_mask(i));
+            shifted.shift_left_one();
+            shifted.masked_xor_assign(&self.poly, shifted.bit_mask(self.degree));
+        }
+
+        product
+    }
+
+    /// `a^(2^n - 2)`, which is the inverse of every non-zero `a`
+    pub fn invert(&self, a: &Gf2nElement) -> Result<Gf2nElement> {
+        if a.is_zero() {
+            return Err(SsssErr::DivisionByZero);
+        }
+
+        let mut square = a.clone();
+        let mut inverse = self.element(1);
+        for _ in 1..self.degree {
+            square = self.mul(&square, &square);
+            inverse = self.mul(&inverse, &square);
+        }
+        Ok(inverse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::GF;
+
+    #[test]
+    fn degree_eight_matches_gf() {
+        // x^8 + x^4 + x^3 + x + 1 is the polynomial of `GF`
+        let field = Gf2n::new(8, [4, 3, 1]);
+
+        for (a, b) in [(3u8, 7u8), (0x53, 0xca), (255, 255), (0, 9)] {
+            let product = field.mul(&field.element(a as u64), &field.element(b as u64));
+            assert_eq!(vec![(GF::new(a) * GF::new(b)).value() as u8], field.export(&product));
+        }
+    }
+
+    #[test]
+    fn invert() {
+        let field = Gf2n::new(128, [7, 2, 1]);
+        let a = field.import(b"sixteen bytes!!!").unwrap();
+
+        assert_eq!(field.element(1), field.mul(&a, &field.invert(&a).unwrap()));
+        assert_eq!(Err(SsssErr::DivisionByZero), field.invert(&field.zero()));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let field = Gf2n::new(72, [7, 4, 2]);
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        assert_eq!(bytes.to_vec(), field.export(&field.import(&bytes).unwrap()));
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 1, 2], field.export(&field.import(&[1, 2]).unwrap()));
+        assert_eq!(Err(SsssErr::SecretTooLong { max: 9 }), field.import(&[0; 10]));
+    }
+}
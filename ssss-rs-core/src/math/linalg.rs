@@ -0,0 +1,74 @@
+use crate::math::GF;
+
+/// Solves a linear system over GF(2^8) by Gaussian elimination
+///
+/// Every row holds the coefficients followed by the right-hand side. Free variables are set to
+/// zero; `None` is returned when the system is inconsistent.
+pub fn solve(mut rows: Vec<Vec<GF>>) -> Option<Vec<GF>> {
+    let num_vars = rows.first().map_or(0, |r| r.len() - 1);
+    let mut pivots = Vec::new();
+    let mut r = 0;
+
+    for col in 0..num_vars {
+        let Some(pivot) = (r..rows.len()).find(|&i| rows[i][col].value() != 0) else { continue };
+        rows.swap(r, pivot);
+
+        let inverse = rows[r][col].invert().ok()?;
+        for value in &mut rows[r][col..] {
+            *value *= inverse;
+        }
+
+        let pivot_row = rows[r].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == r || row[col].value() == 0 { continue; }
+            let factor = row[col];
+            for (value, &p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * p;
+            }
+        }
+
+        pivots.push(col);
+        r += 1;
+    }
+
+    // A remaining row reading `0 = c` with non-zero `c` means there is no solution
+    if rows[r..].iter().any(|row| row[num_vars].value() != 0) {
+        return None;
+    }
+
+    let mut solution = vec![GF::new(0); num_vars];
+    for (row, &col) in pivots.iter().enumerate() {
+        solution[col] = rows[row][num_vars];
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn row(values: &[u8]) -> Vec<GF> {
+        values.iter().copied().map(GF::new).collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        // x + 2y = 5, 3x + y = 9
+        let solution = solve(vec![row(&[1, 2, 5]), row(&[3, 1, 9])]).unwrap();
+
+        assert_eq!(GF::new(5), solution[0] + GF::new(2) * solution[1]);
+        assert_eq!(GF::new(9), GF::new(3) * solution[0] + solution[1]);
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        assert_eq!(None, solve(vec![row(&[1, 1, 5]), row(&[1, 1, 6])]));
+    }
+
+    #[test]
+    fn test_underdetermined() {
+        let solution = solve(vec![row(&[1, 1, 5]), row(&[2, 2, 10])]).unwrap();
+
+        assert_eq!(GF::new(5), solution[0] + solution[1]);
+    }
+}
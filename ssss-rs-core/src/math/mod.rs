@@ -1,5 +1,15 @@
+mod field;
 mod gf;
+mod gf2n;
+mod linalg;
 mod poly;
+mod scalar;
+mod slice;
 
+pub use field::{Field, FieldSize, Gf16, Gf32};
 pub use gf::GF;
+pub use gf2n::{Gf2n, Gf2nElement};
+pub use linalg::solve;
 pub use poly::GfPoly;
+pub use scalar::{interpolate_at_zero, random_scalar, ScalarPoly};
+pub use slice::{add_slice, mul_add_slice, mul_slice};
@@ -1,3 +1,4 @@
+use crate::err::Result;
 use crate::math::GF;
 
 pub struct GfPoly {
@@ -5,27 +6,84 @@ pub struct GfPoly {
 }
 
 impl GfPoly {
-    pub fn new(data: &Vec<u8>) -> GfPoly {
+    pub fn new(data: &[u8]) -> GfPoly {
         GfPoly {
-            data: data.clone()
-                .into_iter()
+            data: data.iter()
+                .copied()
                 .map(GF::new)
                 .collect()
         }
     }
 
-    pub fn apply_x(&self, x: u8) -> GF {
-        let mut val = GF::new(0);
+    /// Polynomial with the given coefficients, lowest first
+    pub fn from_coefficients(data: Vec<GF>) -> GfPoly {
+        GfPoly { data }
+    }
+
+    /// Coefficients, lowest first
+    pub fn coefficients(&self) -> &[GF] {
+        &self.data
+    }
+
+    /// The Lagrange basis for the points `x`: polynomial `i` is one at `x[i]` and zero at all
+    /// other points
+    pub fn lagrange_basis(x: &[u8]) -> Result<Vec<GfPoly>> {
+        x.iter()
+            .enumerate()
+            .map(|(i, &xi)| {
+                let mut data = vec![GF::new(1)];
+                let mut denominator = GF::new(1);
+
+                for (m, &xm) in x.iter().enumerate() {
+                    if i == m { continue; }
+                    // Multiply by (X - x[m])
+                    let mut next = vec![GF::new(0); data.len() + 1];
+                    for (j, &c) in data.iter().enumerate() {
+                        next[j + 1] += c;
+                        next[j] -= c * GF::new(xm);
+                    }
+                    data = next;
+                    denominator *= GF::new(xi) - GF::new(xm);
+                }
+
+                let inverse = denominator.invert()?;
+                Ok(GfPoly::from_coefficients(data.into_iter().map(|c| c * inverse).collect()))
+            })
+            .collect()
+    }
 
-        for i in 0..self.data.len() {
-            let mut term = self.data[i].clone();
-            for _ in 0..i {
-                term *= GF::new(x);
+    /// Degree of the polynomial, `None` for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.data.iter().rposition(|c| c.value() != 0)
+    }
+
+    /// Long division, returning quotient and remainder
+    pub fn div_rem(&self, divisor: &GfPoly) -> Result<(GfPoly, GfPoly)> {
+        let Some(divisor_degree) = divisor.degree() else {
+            return Err(crate::err::SsssErr::DivisionByZero);
+        };
+        let lead_inverse = divisor.data[divisor_degree].invert()?;
+
+        let mut remainder = self.data.clone();
+        let mut quotient = vec![GF::new(0); self.data.len().saturating_sub(divisor_degree).max(1)];
+
+        while let Some(degree) = GfPoly::from_coefficients(remainder.clone()).degree() {
+            if degree < divisor_degree { break; }
+
+            let shift = degree - divisor_degree;
+            let factor = remainder[degree] * lead_inverse;
+            quotient[shift] = factor;
+            for (i, &c) in divisor.data[..=divisor_degree].iter().enumerate() {
+                remainder[i + shift] -= factor * c;
             }
-            val += term;
         }
 
-        val
+        Ok((GfPoly::from_coefficients(quotient), GfPoly::from_coefficients(remainder)))
+    }
+
+    /// Evaluates the polynomial at `x`, by Horner's rule
+    pub fn apply_x(&self, x: u8) -> GF {
+        self.data.iter().rev().fold(GF::new(0), |val, &c| val * GF::new(x) + c)
     }
 }
 
@@ -35,10 +93,53 @@ mod test {
     #[test]
     fn test_apply_x() {
         // 5 + x + 3x^2
-        let poly: GfPoly = GfPoly::new(&vec![5u8, 1u8, 3u8]);
+        let poly: GfPoly = GfPoly::new(&[5u8, 1u8, 3u8]);
 
         assert_eq!(GF::new(5) + GF::new(3) + (GF::new(3) * GF::new(3) * GF::new(3)), poly.apply_x(3));
         assert_eq!(GF::new(5) + GF::new(4) + (GF::new(3) * GF::new(4) * GF::new(4)), poly.apply_x(4));
         assert_eq!(GF::new(5) + GF::new(5) + (GF::new(3) * GF::new(5) * GF::new(5)), poly.apply_x(5));
     }
+
+    #[test]
+    fn test_div_rem() {
+        // (x + 2)(3x + 1) + 7
+        let divisor = GfPoly::new(&[2, 1]);
+        let product = GfPoly::new(&[2, 1]).mul_for_test(&GfPoly::new(&[1, 3]));
+        let dividend = GfPoly::from_coefficients(
+            product.data.iter().enumerate().map(|(i, &c)| if i == 0 { c + GF::new(7) } else { c }).collect(),
+        );
+
+        let (quotient, remainder) = dividend.div_rem(&divisor).unwrap();
+
+        assert_eq!(vec![GF::new(1), GF::new(3)], quotient.data);
+        assert_eq!(Some(0), remainder.degree());
+        assert_eq!(GF::new(7), remainder.data[0]);
+        assert!(dividend.div_rem(&GfPoly::new(&[0])).is_err());
+    }
+
+    #[test]
+    fn test_lagrange_basis() {
+        let x = [1, 5, 9];
+        let basis = GfPoly::lagrange_basis(&x).unwrap();
+
+        for (i, poly) in basis.iter().enumerate() {
+            for (m, &xm) in x.iter().enumerate() {
+                let expected = if i == m { 1 } else { 0 };
+                assert_eq!(GF::new(expected), poly.apply_x(xm));
+            }
+        }
+        assert!(GfPoly::lagrange_basis(&[3, 3]).is_err());
+    }
+
+    impl GfPoly {
+        fn mul_for_test(&self, other: &GfPoly) -> GfPoly {
+            let mut data = vec![GF::new(0); self.data.len() + other.data.len() - 1];
+            for (i, &a) in self.data.iter().enumerate() {
+                for (j, &b) in other.data.iter().enumerate() {
+                    data[i + j] += a * b;
+                }
+            }
+            GfPoly::from_coefficients(data)
+        }
+    }
 }
\ No newline at end of file
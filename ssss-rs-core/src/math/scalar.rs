@@ -0,0 +1,79 @@
+use crate::err::{Result, SsssErr};
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+/// Uniformly random element of the prime-order scalar field of Ristretto255
+pub fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; 64];
+    rand::rng().fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// Polynomial over the scalar field, lowest coefficient first
+pub struct ScalarPoly {
+    data: Vec<Scalar>,
+}
+
+impl ScalarPoly {
+    /// Polynomial with the given constant term and `degree` random higher coefficients
+    pub fn random(constant: Scalar, degree: usize) -> ScalarPoly {
+        let mut data = vec![constant];
+        data.extend((0..degree).map(|_| random_scalar()));
+        ScalarPoly { data }
+    }
+
+    pub fn coefficients(&self) -> &[Scalar] {
+        &self.data
+    }
+
+    pub fn apply_x(&self, x: u32) -> Scalar {
+        let x = Scalar::from(x);
+        self.data.iter().rev().fold(Scalar::ZERO, |acc, c| acc * x + c)
+    }
+}
+
+/// Evaluates the polynomial through the points `(x[i], y[i])` at zero
+pub fn interpolate_at_zero(x: &[u32], y: &[Scalar]) -> Result<Scalar> {
+    if x.len() != y.len() {
+        return Err(SsssErr::MismatchedShardLength { expected: x.len(), found: y.len() });
+    }
+
+    let mut sum = Scalar::ZERO;
+    for j in 0..x.len() {
+        let mut mult = y[j];
+        for m in 0..x.len() {
+            if j == m { continue; }
+            let denominator = Scalar::from(x[m]) - Scalar::from(x[j]);
+            if denominator == Scalar::ZERO {
+                return Err(SsssErr::DivisionByZero);
+            }
+            mult *= Scalar::from(x[m]) * denominator.invert();
+        }
+        sum += mult;
+    }
+
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_x() {
+        let poly = ScalarPoly { data: vec![Scalar::from(5u8), Scalar::from(1u8), Scalar::from(3u8)] };
+
+        // Plain integer arithmetic, as nothing wraps around the group order
+        assert_eq!(Scalar::from(5u8 + 4 + 3 * 16), poly.apply_x(4));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let poly = ScalarPoly::random(Scalar::from(42u8), 2);
+        let x = [3, 7, 9];
+        let y: Vec<Scalar> = x.iter().map(|&x| poly.apply_x(x)).collect();
+
+        assert_eq!(Scalar::from(42u8), interpolate_at_zero(&x, &y).unwrap());
+        assert_eq!(Err(SsssErr::DivisionByZero), interpolate_at_zero(&[3, 3], &y[0..2]));
+    }
+}
@@ -0,0 +1,226 @@
+//! GF(2^8) arithmetic over whole byte buffers
+//!
+//! Each byte is an element; a buffer is multiplied by a single element at once. On x86_64 with
+//! SSSE3 sixteen bytes are done per instruction through nibble tables in a register, elsewhere a
+//! portable fallback does eight bytes per `u64`. Neither indexes memory by the data, so both keep
+//! the constant-time promise of [`GF`].
+
+use crate::math::GF;
+
+/// `data[i] = c * data[i]`
+pub fn mul_slice(c: GF, data: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    if std::is_x86_feature_detected!("ssse3") {
+        // SAFETY: the CPU supports SSSE3, as just checked
+        let rest = unsafe { ssse3::mul(c, data) };
+        return portable::mul(c, rest);
+    }
+
+    portable::mul(c, data);
+}
+
+/// `dst[i] += c * src[i]`, over the shorter of both
+pub fn mul_add_slice(c: GF, src: &[u8], dst: &mut [u8]) {
+    let len = src.len().min(dst.len());
+    let (src, dst) = (&src[..len], &mut dst[..len]);
+
+    #[cfg(target_arch = "x86_64")]
+    if std::is_x86_feature_detected!("ssse3") {
+        // SAFETY: the CPU supports SSSE3, as just checked
+        let done = unsafe { ssse3::mul_add(c, src, dst) };
+        return portable::mul_add(c, &src[done..], &mut dst[done..]);
+    }
+
+    portable::mul_add(c, src, dst);
+}
+
+/// `dst[i] += src[i]`, over the shorter of both
+pub fn add_slice(src: &[u8], dst: &mut [u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+/// Eight elements per `u64`, shifting and masking like [`GF`]'s multiplication does
+mod portable {
+    use super::GF;
+
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    /// The low byte of the field polynomial, `Q - 256`
+    const REDUCE: u64 = 0x1b;
+
+    /// Doubles each of the eight elements
+    fn double(a: u64) -> u64 {
+        let high = a & HIGH_BITS;
+        ((a & !HIGH_BITS) << 1) ^ ((high >> 7) * REDUCE)
+    }
+
+    fn mul_word(c: u8, mut a: u64) -> u64 {
+        let mut product = 0;
+        for i in 0..8 {
+            product ^= a & (((c >> i) & 1) as u64).wrapping_neg();
+            a = double(a);
+        }
+        product
+    }
+
+    /// Applies `f` to every eight bytes of `data`, zero-padding the tail
+    fn for_words(data: &mut [u8], mut f: impl FnMut(usize, u64) -> u64) {
+        for (i, chunk) in data.chunks_mut(8).enumerate() {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let word = f(i * 8, u64::from_le_bytes(word)).to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+
+    fn load(bytes: &[u8]) -> u64 {
+        let mut word = [0u8; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(word)
+    }
+
+    pub fn mul(c: GF, data: &mut [u8]) {
+        let c = c.value() as u8;
+        for_words(data, |_, word| mul_word(c, word));
+    }
+
+    pub fn mul_add(c: GF, src: &[u8], dst: &mut [u8]) {
+        let c = c.value() as u8;
+        for_words(dst, |at, word| word ^ mul_word(c, load(&src[at..src.len().min(at + 8)])));
+    }
+}
+
+/// Sixteen elements per register: the products of `c` with every low and every high nibble are
+/// two 16-byte tables, and `pshufb` looks up all sixteen nibbles at once
+#[cfg(target_arch = "x86_64")]
+mod ssse3 {
+    use super::GF;
+    use std::arch::x86_64::*;
+
+    struct Tables {
+        low: __m128i,
+        high: __m128i,
+        nibble: __m128i,
+    }
+
+    #[target_feature(enable = "ssse3")]
+    fn tables(c: GF) -> Tables {
+        let mut low = [0u8; 16];
+        let mut high = [0u8; 16];
+        for n in 0..16u8 {
+            low[n as usize] = (c * GF::new(n)).value() as u8;
+            high[n as usize] = (c * GF::new(n << 4)).value() as u8;
+        }
+        // SAFETY: both arrays are 16 bytes, and unaligned loads are allowed
+        unsafe {
+            Tables {
+                low: _mm_loadu_si128(low.as_ptr().cast()),
+                high: _mm_loadu_si128(high.as_ptr().cast()),
+                nibble: _mm_set1_epi8(0x0f),
+            }
+        }
+    }
+
+    #[target_feature(enable = "ssse3")]
+    fn mul_register(t: &Tables, v: __m128i) -> __m128i {
+        let low = _mm_and_si128(v, t.nibble);
+        let high = _mm_and_si128(_mm_srli_epi64(v, 4), t.nibble);
+        _mm_xor_si128(_mm_shuffle_epi8(t.low, low), _mm_shuffle_epi8(t.high, high))
+    }
+
+    /// Multiplies all whole registers of `data`, returning the bytes left over
+    #[target_feature(enable = "ssse3")]
+    pub fn mul(c: GF, data: &mut [u8]) -> &mut [u8] {
+        let t = tables(c);
+        let mut chunks = data.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            // SAFETY: the chunk is exactly 16 bytes
+            unsafe {
+                let v = _mm_loadu_si128(chunk.as_ptr().cast());
+                _mm_storeu_si128(chunk.as_mut_ptr().cast(), mul_register(&t, v));
+            }
+        }
+        chunks.into_remainder()
+    }
+
+    /// Multiply-adds all whole registers, returning how many bytes were done
+    #[target_feature(enable = "ssse3")]
+    pub fn mul_add(c: GF, src: &[u8], dst: &mut [u8]) -> usize {
+        let t = tables(c);
+        let done = src.len().min(dst.len()) / 16 * 16;
+        for (s, d) in src[..done].chunks_exact(16).zip(dst[..done].chunks_exact_mut(16)) {
+            // SAFETY: both chunks are exactly 16 bytes
+            unsafe {
+                let product = mul_register(&t, _mm_loadu_si128(s.as_ptr().cast()));
+                let sum = _mm_xor_si128(_mm_loadu_si128(d.as_ptr().cast()), product);
+                _mm_storeu_si128(d.as_mut_ptr().cast(), sum);
+            }
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        rand::rng().fill_bytes(&mut bytes);
+        bytes
+    }
+
+    fn expected_mul(c: GF, data: &[u8]) -> Vec<u8> {
+        data.iter().map(|&b| (c * GF::new(b)).value() as u8).collect()
+    }
+
+    #[test]
+    fn mul_matches_gf() {
+        // Every byte value once, plus lengths around the register and word sizes
+        let all: Vec<u8> = (0..=255).collect();
+        for c in 0..=255 {
+            let c = GF::new(c);
+            for data in [all.clone(), random_bytes(0), random_bytes(7), random_bytes(17), random_bytes(63)] {
+                let mut product = data.clone();
+                mul_slice(c, &mut product);
+                assert_eq!(expected_mul(c, &data), product);
+
+                let mut portable = data.clone();
+                portable::mul(c, &mut portable);
+                assert_eq!(product, portable);
+            }
+        }
+    }
+
+    #[test]
+    fn mul_add_matches_gf() {
+        for c in 0..=255 {
+            let c = GF::new(c);
+            for len in [0, 5, 16, 41] {
+                let (src, dst) = (random_bytes(len), random_bytes(len));
+                let expected: Vec<u8> = expected_mul(c, &src).iter().zip(&dst).map(|(p, d)| p ^ d).collect();
+
+                let mut sum = dst.clone();
+                mul_add_slice(c, &src, &mut sum);
+                assert_eq!(expected, sum);
+
+                let mut portable = dst.clone();
+                portable::mul_add(c, &src, &mut portable);
+                assert_eq!(expected, portable);
+            }
+        }
+    }
+
+    #[test]
+    fn mismatched_lengths() {
+        let mut dst = vec![1u8; 20];
+        mul_add_slice(GF::new(1), &[2u8; 18], &mut dst);
+        assert_eq!([3u8; 18], dst[..18]);
+        assert_eq!([1u8; 2], dst[18..]);
+
+        add_slice(&[1u8; 30], &mut dst);
+        assert_eq!([2u8; 18], dst[..18]);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::err::{Result, SsssErr};
+use crate::math::{Field, FieldSize, Gf16, Gf32, GF};
+use crate::{for_chunks, integrity, unpad, validate_shards, SsssShard};
+use std::collections::HashSet;
+
+/// Restores secrets from the data of a fixed set of shard numbers
+///
+/// The Lagrange weight of every shard depends on the shard numbers alone, so it is computed once
+/// when the reconstructor is built; every payload is then a weighted sum of the shards' data.
+///
+/// ```
+/// use ssss_rs_core::{encode, Reconstructor, ShamirScheme};
+///
+/// let reconstructor = Reconstructor::new(&[2, 3]).unwrap();
+/// for secret in [b"first".as_slice(), b"second"] {
+///     let shards = encode(&ShamirScheme::new(2, 3), secret).unwrap();
+///     assert_eq!(secret, reconstructor.decode(&shards[1..]).unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Reconstructor {
+    numbers: Vec<u32>,
+    field: FieldSize,
+    /// The weights as elements of `field`
+    weights: Vec<u32>,
+}
+
+impl Reconstructor {
+    /// For GF(2^8) shards with the given numbers, which must be non-zero and unique
+    pub fn new(shard_numbers: &[u32]) -> Result<Reconstructor> {
+        Reconstructor::for_field(FieldSize::Gf8, shard_numbers)
+    }
+
+    /// For shards of a split in `field` with the given numbers, which must be non-zero, unique
+    /// and elements of the field
+    pub fn for_field(field: FieldSize, shard_numbers: &[u32]) -> Result<Reconstructor> {
+        if shard_numbers.is_empty() {
+            return Err(SsssErr::InsufficientShards { needed: 1, got: 0 });
+        }
+
+        let mut seen = HashSet::new();
+        for &number in shard_numbers {
+            if number == 0 || number > field.max_shards() {
+                return Err(SsssErr::InvalidShardNumber(number));
+            }
+            if !seen.insert(number) {
+                return Err(SsssErr::DuplicateShard(number));
+            }
+        }
+
+        let weights = match field {
+            FieldSize::Gf8 => lagrange_at_zero::<GF>(shard_numbers)?,
+            FieldSize::Gf16 => lagrange_at_zero::<Gf16>(shard_numbers)?,
+            FieldSize::Gf32 => lagrange_at_zero::<Gf32>(shard_numbers)?,
+        };
+        Ok(Reconstructor { numbers: shard_numbers.to_vec(), field, weights })
+    }
+
+    /// The shard numbers, in the order `reconstruct` expects their data
+    pub fn shard_numbers(&self) -> &[u32] {
+        &self.numbers
+    }
+
+    pub fn field(&self) -> FieldSize {
+        self.field
+    }
+
+    /// Restores a payload from the data of every shard, given in the order of the shard numbers
+    pub fn reconstruct<D: AsRef<[u8]> + Sync>(&self, data: &[D]) -> Result<Vec<u8>> {
+        if data.len() != self.numbers.len() {
+            return Err(SsssErr::WrongNumberOfShards { expected: self.numbers.len(), got: data.len() });
+        }
+        let len = data[0].as_ref().len();
+        if let Some(d) = data.iter().find(|d| d.as_ref().len() != len) {
+            return Err(SsssErr::MismatchedShardLength { expected: len, found: d.as_ref().len() });
+        }
+        if len % self.field.element_len() != 0 {
+            return Err(SsssErr::MalformedShard(format!("the data is not a whole number of {} elements", self.field)));
+        }
+
+        match self.field {
+            FieldSize::Gf8 => Ok(self.combine::<GF, D>(data)),
+            FieldSize::Gf16 => Ok(self.combine::<Gf16, D>(data)),
+            FieldSize::Gf32 => Ok(self.combine::<Gf32, D>(data)),
+        }
+    }
+
+    /// The weighted sum of the data, whose lengths are checked
+    fn combine<F: Field, D: AsRef<[u8]> + Sync>(&self, data: &[D]) -> Vec<u8> {
+        let mut payload = vec![0u8; data[0].as_ref().len()];
+        for_chunks(&mut payload, |at, chunk| {
+            for (&weight, data) in self.weights.iter().zip(data) {
+                F::from_u32(weight).mul_add_slice(&data.as_ref()[at..], chunk);
+            }
+        });
+        payload
+    }
+
+    /// Restores the secret from shards with exactly these numbers, in any order, like
+    /// [`decode`](crate::decode)
+    pub fn decode(&self, shards: &[SsssShard]) -> Result<Vec<u8>> {
+        validate_shards(shards)?;
+        if shards[0].field() != self.field {
+            return Err(SsssErr::UnsupportedField(shards[0].field()));
+        }
+        if shards.len() != self.numbers.len() {
+            return Err(SsssErr::WrongNumberOfShards { expected: self.numbers.len(), got: shards.len() });
+        }
+
+        let data = self
+            .numbers
+            .iter()
+            .map(|&n| shards.iter().find(|s| s.num() == n).map(SsssShard::data))
+            .collect::<Option<Vec<_>>>();
+        let Some(data) = data else {
+            let unknown = shards.iter().find(|s| !self.numbers.contains(&s.num())).expect("a number is missing");
+            return Err(SsssErr::InvalidShardNumber(unknown.num()));
+        };
+
+        let mut secret = self.reconstruct(&data)?;
+        let checked = shards[0].set().filter(|set| set.checksum);
+        if self.field != FieldSize::Gf8 {
+            // A wrong shard garbles the padding as well, which the check should report
+            secret = unpad(secret).map_err(|e| if checked.is_some() { SsssErr::IntegrityCheckFailed } else { e })?;
+        }
+        match checked {
+            Some(set) => integrity::open(set.id, secret),
+            None => Ok(secret),
+        }
+    }
+}
+
+/// The weights of the points `x` in the Lagrange interpolation at zero; branches only on these
+/// public x values, never on the secret y values
+fn lagrange_at_zero<F: Field>(x: &[u32]) -> Result<Vec<u32>> {
+    let k = x.len();
+    (0..k)
+        .map(|j| {
+            let mut weight = F::from_u32(1);
+            for m in 0..k {
+                if j == m { continue; }
+                weight *= F::from_u32(x[m]) * (F::from_u32(x[m]) - F::from_u32(x[j])).invert()?;
+            }
+            Ok(weight.to_u32())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{split_bytes, ShamirScheme};
+    use test_case::test_case;
+
+    #[test]
+    fn reconstructs_many_payloads() {
+        let scheme = ShamirScheme::new(3, 5);
+        let reconstructor = Reconstructor::new(&[5, 1, 3]).unwrap();
+        assert_eq!(&[5, 1, 3], reconstructor.shard_numbers());
+
+        for secret in [b"a".to_vec(), b"payload".to_vec(), vec![0xa5; 1000]] {
+            let shares = split_bytes(&scheme, &secret);
+            let data = [&shares[4], &shares[0], &shares[2]];
+            assert_eq!(secret, reconstructor.reconstruct(&data).unwrap());
+        }
+    }
+
+    #[test]
+    fn single_byte() {
+        let shares = split_bytes(&ShamirScheme::new(2, 2), &[42]);
+        assert_eq!(vec![42], Reconstructor::new(&[1, 2]).unwrap().reconstruct(&shares).unwrap());
+    }
+
+    #[test_case(&[], SsssErr::InsufficientShards { needed: 1, got: 0 }; "no shards")]
+    #[test_case(&[1, 0], SsssErr::InvalidShardNumber(0); "zero")]
+    #[test_case(&[2, 1, 2], SsssErr::DuplicateShard(2); "duplicate")]
+    #[test_case(&[256, 1], SsssErr::InvalidShardNumber(256); "outside the field")]
+    fn invalid_numbers(numbers: &[u32], expected: SsssErr) {
+        assert_eq!(expected, Reconstructor::new(numbers).unwrap_err());
+    }
+
+    #[test]
+    fn wide_fields() {
+        let scheme = ShamirScheme::new(2, 70_000);
+        assert_eq!(FieldSize::Gf32, scheme.field());
+        let shares = split_bytes(&scheme, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let reconstructor = Reconstructor::for_field(FieldSize::Gf32, &[69_999, 7]).unwrap();
+        assert_eq!(FieldSize::Gf32, reconstructor.field());
+        let data = [&shares[69_998], &shares[6]];
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8], reconstructor.reconstruct(&data).unwrap());
+        assert!(matches!(reconstructor.reconstruct(&[&[1u8, 2][..], &[3, 4]]), Err(SsssErr::MalformedShard(_))));
+
+        let shards = crate::encode(&ShamirScheme::new(2, 3), b"narrow").unwrap();
+        let reconstructor = Reconstructor::for_field(FieldSize::Gf16, &[1, 2]).unwrap();
+        assert_eq!(Err(SsssErr::UnsupportedField(FieldSize::Gf8)), reconstructor.decode(&shards[..2]));
+    }
+
+    #[test]
+    fn decodes_shards_in_any_order() {
+        let reconstructor = Reconstructor::new(&[1, 3]).unwrap();
+        let mut shards = crate::encode(&ShamirScheme::new(2, 3).with_integrity_check(), b"order").unwrap();
+
+        assert_eq!(Err(SsssErr::WrongNumberOfShards { expected: 2, got: 3 }), reconstructor.decode(&shards));
+        assert_eq!(Err(SsssErr::InvalidShardNumber(2)), reconstructor.decode(&shards[..2]));
+
+        shards.remove(1);
+        shards.reverse();
+        assert_eq!(b"order".to_vec(), reconstructor.decode(&shards).unwrap());
+    }
+
+    #[test]
+    fn invalid_payloads() {
+        let reconstructor = Reconstructor::new(&[1, 2]).unwrap();
+        assert_eq!(
+            Err(SsssErr::WrongNumberOfShards { expected: 2, got: 1 }),
+            reconstructor.reconstruct(&[[3u8]])
+        );
+        assert_eq!(
+            Err(SsssErr::MismatchedShardLength { expected: 1, found: 2 }),
+            reconstructor.reconstruct(&[&[3u8][..], &[4, 5]])
+        );
+    }
+}
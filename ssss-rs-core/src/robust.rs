@@ -0,0 +1,167 @@
+//! Error-correcting reconstruction
+//!
+//! The shards of a split form a Reed-Solomon codeword: per byte, the values of a polynomial of
+//! degree `threshold - 1` at the shard numbers. Given `n` shards, Berlekamp-Welch recovers that
+//! polynomial as long as at most `(n - threshold) / 2` of them are wrong, and thereby tells which
+//! ones are.
+
+use crate::err::{Result, SsssErr};
+use crate::math::{solve, FieldSize, GfPoly, GF};
+use crate::{integrity, validate_shards, SsssShard};
+
+/// Outcome of [`decode_robust`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RobustDecoding {
+    pub secret: Vec<u8>,
+    /// Numbers of the shards that disagreed with the others, in ascending order
+    pub faulty_shards: Vec<u32>,
+}
+
+/// Restores the secret while correcting up to `(n - threshold) / 2` corrupted shards
+///
+/// The threshold is taken from the shard metadata, so legacy shards cannot be decoded this way,
+/// and neither can splits over a field wider than GF(2^8).
+/// When more shards are corrupt than can be corrected, [`SsssErr::TooManyFaultyShards`] is
+/// returned.
+pub fn decode_robust(shards: &[SsssShard]) -> Result<RobustDecoding> {
+    validate_shards(shards)?;
+    let set = shards[0].set().ok_or(SsssErr::UnknownThreshold)?;
+    if set.field != FieldSize::Gf8 {
+        return Err(SsssErr::UnsupportedField(set.field));
+    }
+
+    // Every number is below 256 in GF(2^8), as the shards are validated
+    let x: Vec<u8> = shards.iter().map(|s| s.num() as u8).collect();
+    let mut faulty = vec![false; shards.len()];
+    let mut secret = Vec::with_capacity(shards[0].data().len());
+
+    for i in 0..shards[0].data().len() {
+        let y: Vec<u8> = shards.iter().map(|s| s.data()[i]).collect();
+        let polynomial = berlekamp_welch(&x, &y, set.threshold as usize)?;
+
+        for (j, (&x, &y)) in x.iter().zip(&y).enumerate() {
+            if polynomial.apply_x(x) != GF::new(y) {
+                faulty[j] = true;
+            }
+        }
+        secret.push(polynomial.apply_x(0).value() as u8);
+    }
+
+    let secret = if set.checksum { integrity::open(set.id, secret)? } else { secret };
+
+    let mut faulty_shards: Vec<u32> = x.iter().zip(&faulty).filter(|&(_, &f)| f).map(|(&x, _)| x.into()).collect();
+    faulty_shards.sort_unstable();
+
+    Ok(RobustDecoding { secret, faulty_shards })
+}
+
+/// Finds the polynomial of degree below `k` that passes through all but at most `(n - k) / 2`
+/// of the points `(x[i], y[i])`
+fn berlekamp_welch(x: &[u8], y: &[u8], k: usize) -> Result<GfPoly> {
+    let n = x.len();
+    let e = (n - k) / 2;
+
+    // Unknowns: the k + e coefficients of Q, followed by the lower e coefficients of the monic
+    // error locator E. Every point gives the equation Q(x) - y * E(x) = 0.
+    let rows = x
+        .iter()
+        .zip(y)
+        .map(|(&x, &y)| {
+            let (x, y) = (GF::new(x), GF::new(y));
+            let powers: Vec<GF> = std::iter::successors(Some(GF::new(1)), |&p| Some(p * x))
+                .take(k + e + 1)
+                .collect();
+
+            let mut row = powers[..k + e].to_vec();
+            row.extend(powers[..e].iter().map(|&p| -(y * p)));
+            row.push(y * powers[e]);
+            row
+        })
+        .collect();
+
+    let solution = solve(rows).ok_or(SsssErr::TooManyFaultyShards)?;
+
+    let q = GfPoly::from_coefficients(solution[..k + e].to_vec());
+    let mut locator = solution[k + e..].to_vec();
+    locator.push(GF::new(1));
+
+    let (p, remainder) = q.div_rem(&GfPoly::from_coefficients(locator))?;
+    if remainder.degree().is_some() || p.degree().is_some_and(|d| d >= k) {
+        return Err(SsssErr::TooManyFaultyShards);
+    }
+
+    Ok(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, ShamirScheme};
+
+    fn corrupt(shard: &SsssShard) -> SsssShard {
+        let text = shard.to_string();
+        let position = text.rfind('-').unwrap() + 1;
+        let flipped = if &text[position..=position] == "A" { "B" } else { "A" };
+        let mut text = text;
+        text.replace_range(position..=position, flipped);
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn no_errors() {
+        let shards = encode(&ShamirScheme::new(3, 5), b"paper backup").unwrap();
+
+        let decoded = decode_robust(&shards).unwrap();
+
+        assert_eq!(b"paper backup".to_vec(), decoded.secret);
+        assert!(decoded.faulty_shards.is_empty());
+    }
+
+    #[test]
+    fn corrects_errors() {
+        let mut shards = encode(&ShamirScheme::new(3, 7), b"paper backup").unwrap();
+        shards[1] = corrupt(&shards[1]);
+        shards[5] = corrupt(&shards[5]);
+
+        let decoded = decode_robust(&shards).unwrap();
+
+        assert_eq!(b"paper backup".to_vec(), decoded.secret);
+        assert_eq!(vec![2, 6], decoded.faulty_shards);
+    }
+
+    #[test]
+    fn too_many_errors() {
+        let mut shards = encode(&ShamirScheme::new(3, 6), b"paper backup").unwrap();
+        shards[0] = corrupt(&shards[0]);
+        shards[4] = corrupt(&shards[4]);
+
+        assert_eq!(Err(SsssErr::TooManyFaultyShards), decode_robust(&shards));
+    }
+
+    #[test]
+    fn detects_single_error_without_correcting() {
+        let mut shards = encode(&ShamirScheme::new(3, 4), b"paper backup").unwrap();
+        shards[2] = corrupt(&shards[2]);
+
+        assert_eq!(Err(SsssErr::TooManyFaultyShards), decode_robust(&shards));
+    }
+
+    #[test]
+    fn with_integrity_check() {
+        let options = ShamirScheme::new(2, 5).with_integrity_check();
+        let mut shards = encode(&options, b"paper backup").unwrap();
+        shards[3] = corrupt(&shards[3]);
+
+        let decoded = decode_robust(&shards).unwrap();
+
+        assert_eq!(b"paper backup".to_vec(), decoded.secret);
+        assert_eq!(vec![4], decoded.faulty_shards);
+    }
+
+    #[test]
+    fn legacy_shards() {
+        let shards: Vec<SsssShard> = vec!["1-SWg=".parse().unwrap(), "2-Sms=".parse().unwrap()];
+
+        assert_eq!(Err(SsssErr::UnknownThreshold), decode_robust(&shards));
+    }
+}
@@ -0,0 +1,223 @@
+//! `Serialize` and `Deserialize` for [`SsssShard`], behind the `serde` feature
+//!
+//! By default a shard becomes its textual form with base64 data in human-readable formats such as
+//! JSON, and its [binary form](SsssShard::to_bytes) in compact ones such as CBOR. The modules here
+//! pick another representation for a field, with `#[serde(with = "...")]`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Backup {
+//!     #[serde(with = "ssss_rs_core::serialization::bech32m")]
+//!     shard: SsssShard,
+//! }
+//! ```
+
+use crate::encoding::{Base32, Base64, Base64Url, Bech32m, Encoding, Hex, Words, ZBase32};
+use crate::SsssShard;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+impl Serialize for SsssShard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string_with(&Base64))
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SsssShard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ShardVisitor(&Base64))
+        } else {
+            deserializer.deserialize_bytes(ShardVisitor(&Base64))
+        }
+    }
+}
+
+/// Accepts a shard as text in the given encoding, or in its binary form
+struct ShardVisitor<'a>(&'a dyn Encoding);
+
+impl<'de> Visitor<'de> for ShardVisitor<'_> {
+    type Value = SsssShard;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a shard as text or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<SsssShard, E> {
+        SsssShard::parse_with(v, self.0).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<SsssShard, E> {
+        SsssShard::from_bytes(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SsssShard, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+macro_rules! text_representation {
+    ($(#[$doc:meta])* $name:ident, $encoding:expr) => {
+        $(#[$doc])*
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<S: Serializer>(shard: &SsssShard, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&shard.to_string_with(&$encoding))
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SsssShard, D::Error> {
+                deserializer.deserialize_str(ShardVisitor(&$encoding))
+            }
+        }
+    };
+}
+
+text_representation!(
+    /// Text with standard base64 data, in every format
+    base64, Base64
+);
+text_representation!(
+    /// Text with URL-safe base64 data
+    base64url, Base64Url
+);
+text_representation!(
+    /// Text with base32 data
+    base32, Base32
+);
+text_representation!(
+    /// Text with z-base-32 data
+    zbase32, ZBase32
+);
+text_representation!(
+    /// Text with hexadecimal data
+    hex, Hex
+);
+text_representation!(
+    /// Text with Bech32m data
+    bech32m, Bech32m::default()
+);
+text_representation!(
+    /// Text with the data spelled in words
+    words, Words
+);
+
+/// The binary form, in every format; a byte array where the format lacks bytes
+pub mod binary {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(shard: &SsssShard, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&shard.to_bytes())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SsssShard, D::Error> {
+        deserializer.deserialize_bytes(ShardVisitor(&Base64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, FieldSize, ShamirScheme, SsssShard};
+    use serde::{Deserialize, Serialize};
+
+    fn to_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn from_cbor<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> T {
+        ciborium::from_reader(bytes).unwrap()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let shards = encode(&ShamirScheme::new(2, 3).with_integrity_check(), b"json").unwrap();
+        let json = serde_json::to_string(&shards).unwrap();
+
+        assert_eq!(format!("[\"{}\",\"{}\",\"{}\"]", shards[0], shards[1], shards[2]), json);
+
+        let parsed: Vec<SsssShard> = serde_json::from_str(&json).unwrap();
+        assert_eq!(b"json".to_vec(), decode(&parsed[1..]).unwrap());
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        let shards = encode(&ShamirScheme::new(2, 3), b"cbor").unwrap();
+        let cbor = to_cbor(&shards);
+
+        // An array of three byte strings, each the binary form
+        assert_eq!(0x83, cbor[0]);
+        assert!(cbor.windows(4).any(|w| w == b"SSSS"));
+
+        let parsed: Vec<SsssShard> = from_cbor(&cbor);
+        assert_eq!(b"cbor".to_vec(), decode(&parsed[..2]).unwrap());
+    }
+
+    #[test]
+    fn scheme_round_trip() {
+        let scheme = ShamirScheme::new(3, 5).with_integrity_check();
+        let json = serde_json::to_string(&scheme).unwrap();
+        assert_eq!(r#"{"num_shards":5,"threshold":3,"integrity_check":true}"#, json);
+
+        for parsed in [serde_json::from_str::<ShamirScheme>(&json).unwrap(), from_cbor(&to_cbor(&scheme))] {
+            assert_eq!((3, 5, true), (parsed.threshold, parsed.num_shards, parsed.integrity_check));
+        }
+
+        let defaulted: ShamirScheme = serde_json::from_str(r#"{"threshold":2,"num_shards":300}"#).unwrap();
+        assert!(!defaulted.integrity_check);
+        assert_eq!(FieldSize::Gf16, defaulted.field());
+
+        let forced = serde_json::to_string(&scheme.with_field(FieldSize::Gf32)).unwrap();
+        assert!(forced.ends_with(r#","field":"gf32"}"#));
+        assert_eq!(FieldSize::Gf32, serde_json::from_str::<ShamirScheme>(&forced).unwrap().field());
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Backup {
+        #[serde(with = "super::hex")]
+        hex: SsssShard,
+        #[serde(with = "super::words")]
+        words: SsssShard,
+        #[serde(with = "super::binary")]
+        binary: SsssShard,
+    }
+
+    #[test]
+    fn configurable_representation() {
+        let mut shards = encode(&ShamirScheme::new(3, 3), b"with").unwrap().into_iter();
+        let backup = Backup { hex: shards.next().unwrap(), words: shards.next().unwrap(), binary: shards.next().unwrap() };
+
+        let json: serde_json::Value = serde_json::to_value(&backup).unwrap();
+        assert!(json["hex"].as_str().unwrap().ends_with(&format!("-{}", hex_data(&backup.hex))));
+        // Four data words and the checksum word
+        assert_eq!(5, json["words"].as_str().unwrap().split(' ').count());
+        assert_eq!(backup.binary.to_bytes().len(), json["binary"].as_array().unwrap().len());
+
+        for parsed in [serde_json::from_value::<Backup>(json).unwrap(), from_cbor(&to_cbor(&backup))] {
+            let shards = [parsed.hex, parsed.words, parsed.binary];
+            assert_eq!(b"with".to_vec(), decode(&shards).unwrap());
+        }
+    }
+
+    fn hex_data(shard: &SsssShard) -> String {
+        shard.data().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn errors() {
+        assert!(serde_json::from_str::<SsssShard>("\"01-QQ!=\"").is_err());
+        assert!(serde_json::from_str::<SsssShard>("[83, 83, 83, 83]").is_err());
+        assert!(serde_json::from_str::<SsssShard>("7").is_err());
+    }
+}
@@ -1,65 +1,384 @@
-use crate::encoding::{base64_decode, base64_encode};
+use crate::encoding::{Base64, Encoding};
+use crate::err::SsssErr;
+use crate::integrity::crc32;
+use crate::math::FieldSize;
 
 use std::fmt;
 use std::str;
 
+/// Version of the shard format written by [`SsssShard`]'s `Display`
+pub const FORMAT_VERSION: u8 = 2;
+
+/// Flag marking that the shared payload ends with a digest of the secret
+const FLAG_CHECKSUM: u8 = 0x01;
+
+/// Flags marking the split was computed in a wider field than GF(2^8)
+const FLAG_GF16: u8 = 0x02;
+const FLAG_GF32: u8 = 0x04;
+const FIELD_FLAGS: u8 = FLAG_GF16 | FLAG_GF32;
+
+/// Flag marking that a binary shard carries the fields of its [`ShardSet`]; only legacy shards
+/// lack them
+const FLAG_SET: u8 = 0x80;
+
+/// First bytes of every binary shard
+const BINARY_MAGIC: [u8; 4] = *b"SSSS";
+
+/// Version of the binary layout written by [`SsssShard::to_bytes`] for splits in the wider
+/// fields; GF(2^8) shards are still written as version 1
+pub const BINARY_VERSION: u8 = 2;
+
+/// The split a shard belongs to: all shards of one `encode` call share these
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ShardSet {
+    pub(crate) id: u32,
+    pub(crate) threshold: u32,
+    pub(crate) num_shards: u32,
+    pub(crate) checksum: bool,
+    pub(crate) field: FieldSize,
+}
+
+impl ShardSet {
+    fn flags(&self) -> u8 {
+        let checksum = if self.checksum { FLAG_CHECKSUM } else { 0 };
+        let field = match self.field {
+            FieldSize::Gf8 => 0,
+            FieldSize::Gf16 => FLAG_GF16,
+            FieldSize::Gf32 => FLAG_GF32,
+        };
+        checksum | field
+    }
+}
+
+/// The field named by the field bits of `flags`
+fn field_from_flags(flags: u8) -> Result<FieldSize, SsssErr> {
+    match flags & FIELD_FLAGS {
+        0 => Ok(FieldSize::Gf8),
+        FLAG_GF16 => Ok(FieldSize::Gf16),
+        FLAG_GF32 => Ok(FieldSize::Gf32),
+        _ => Err(SsssErr::MalformedShard(format!("conflicting field flags {flags:02x}"))),
+    }
+}
+
 #[derive(Debug)]
 pub struct SsssShard {
-    shard_poolsize: Option<u8>,
-    shard_number: u8,
+    shard_poolsize: Option<u32>,
+    shard_number: u32,
     data: Vec<u8>,
+    version: u8,
+    /// `None` for shards parsed from the legacy `NN-base64` format
+    set: Option<ShardSet>,
 }
 
 impl SsssShard {
-    pub (crate) fn new(total_shards: u8, n: u8, data: Vec<u8>) -> Self {
+    pub (crate) fn new(set: ShardSet, n: u32, data: Vec<u8>) -> Self {
         SsssShard {
-            shard_poolsize: Some(total_shards),
+            shard_poolsize: Some(set.num_shards),
             shard_number: n,
             data: data.to_vec(),
+            version: FORMAT_VERSION,
+            set: Some(set),
         }
     }
 
     pub (crate) fn data(&self) -> &[u8] { self.data.as_slice() }
-    pub (crate) fn num(&self) -> u8 { self.shard_number }
+    pub (crate) fn num(&self) -> u32 { self.shard_number }
+    pub (crate) fn set(&self) -> Option<ShardSet> { self.set }
+
+    /// The number of this shard, its x-coordinate
+    pub fn number(&self) -> u32 { self.shard_number }
+
+    /// Format version the shard was read from, 0 for the legacy `NN-base64` format
+    pub fn version(&self) -> u8 { self.version }
+
+    /// Random identifier shared by all shards of the same split
+    pub fn set_id(&self) -> Option<u32> { self.set.map(|s| s.id) }
+
+    /// Number of shards needed to restore the secret
+    pub fn threshold(&self) -> Option<u32> { self.set.map(|s| s.threshold) }
+
+    /// Number of shards created in the split
+    pub fn num_shards(&self) -> Option<u32> { self.set.map(|s| s.num_shards) }
+
+    /// The field the split was computed in, GF(2^8) for legacy shards
+    pub fn field(&self) -> FieldSize { self.set.map_or(FieldSize::Gf8, |s| s.field) }
+
+    /// Whether the secret is protected by an integrity check
+    pub fn has_checksum(&self) -> bool { self.set.is_some_and(|s| s.checksum) }
 }
 
-impl fmt::Display for SsssShard {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let data_formatted = base64_encode(self.data.as_slice()).unwrap();
+impl SsssShard {
+    /// Everything that precedes the data in the textual representation, up to and including the
+    /// last `-`
+    pub(crate) fn header(&self) -> String {
+        if let Some(set) = self.set {
+            let width = set.num_shards.to_string().len();
+            return format!(
+                "v{}-{:08x}-{:02x}-{}-{}-{:0width$}-",
+                FORMAT_VERSION,
+                set.id,
+                set.flags(),
+                set.threshold,
+                set.num_shards,
+                self.shard_number,
+                width = width
+            );
+        }
 
         let width = match self.shard_poolsize {
             Some(x) => (x as f64).log10().ceil(),
             None => (self.shard_number as f64).log10().ceil(),
         } as usize;
 
-        write!(
-            f,
-            "{:0width$}-{}",
-            self.shard_number,
-            data_formatted,
-            width = width
-        )
+        format!("{:0width$}-", self.shard_number, width = width)
     }
 }
 
-const PARSE_ERR: &'static str = "Cannot parse Shard";
+impl SsssShard {
+    /// The textual representation, with the data in the given encoding
+    pub fn to_string_with(&self, encoding: &dyn Encoding) -> String {
+        format!("{}{}", self.header(), encoding.encode(&self.data))
+    }
 
-impl str::FromStr for SsssShard {
-    type Err = String;
+    /// Parses a shard written by [`SsssShard::to_string_with`] with the same encoding
+    pub fn parse_with(s: &str, encoding: &dyn Encoding) -> Result<Self, SsssErr> {
+        parse(s, encoding)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let split: Vec<&str> = s.split('-').collect();
+impl SsssShard {
+    /// The binary representation, for storage where every byte counts
+    ///
+    /// The layout, multi-byte integers big-endian:
+    ///
+    /// | bytes | field |
+    /// |-------|-------|
+    /// | 4 | magic `SSSS` |
+    /// | 1 | binary format version, 1 for GF(2^8) and [`BINARY_VERSION`] for wider fields |
+    /// | 1 | flags: `0x01` integrity check, `0x02` GF(2^16), `0x04` GF(2^32), `0x80` set fields present |
+    /// | 1, or 4 from version 2 | shard number |
+    /// | 4 + 1 + 1, or 4 + 4 + 4 from version 2 | set id, threshold and number of shards, if flagged |
+    /// | 4 | data length |
+    /// | n | data |
+    /// | 4 | CRC-32 of all preceding bytes |
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let flags = self.set.map_or(0, |set| set.flags() | FLAG_SET);
 
-        if split.len() != 2 { return Err(PARSE_ERR.into()); }
+        let mut bytes = BINARY_MAGIC.to_vec();
+        if self.field() == FieldSize::Gf8 {
+            bytes.extend_from_slice(&[1, flags, self.shard_number as u8]);
+            if let Some(set) = self.set {
+                bytes.extend_from_slice(&set.id.to_be_bytes());
+                bytes.extend_from_slice(&[set.threshold as u8, set.num_shards as u8]);
+            }
+        } else {
+            let set = self.set.expect("only shards of a set have a wide field");
+            bytes.extend_from_slice(&[BINARY_VERSION, flags]);
+            for value in [self.shard_number, set.id, set.threshold, set.num_shards] {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        bytes.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes.extend_from_slice(&crc32(&bytes).to_be_bytes());
+        bytes
+    }
+
+    /// Reads a shard written by [`SsssShard::to_bytes`]
+    ///
+    /// The CRC is checked before anything else, so a corrupted shard is reported as
+    /// [`SsssErr::InvalidChecksum`] rather than restoring a wrong secret.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SsssErr> {
+        let Some(body) = bytes.strip_prefix(&BINARY_MAGIC) else {
+            return Err(SsssErr::MalformedShard("not a binary shard".into()));
+        };
+        let Some((body, crc)) = body.split_last_chunk::<4>() else {
+            return Err(SsssErr::MalformedShard("binary shard is truncated".into()));
+        };
+        if crc32(&bytes[..bytes.len() - 4]) != u32::from_be_bytes(*crc) {
+            return Err(SsssErr::InvalidChecksum);
+        }
+
+        let mut reader = ByteReader(body);
+        let version = reader.byte()?;
+        let wide = match version {
+            1 => false,
+            BINARY_VERSION => true,
+            _ => return Err(SsssErr::UnsupportedVersion(version)),
+        };
+        let flags = reader.byte()?;
+        let known = if wide { FLAG_CHECKSUM | FIELD_FLAGS | FLAG_SET } else { FLAG_CHECKSUM | FLAG_SET };
+        if flags & !known != 0 {
+            return Err(SsssErr::MalformedShard(format!("unknown flags {flags:02x}")));
+        }
+        let field = field_from_flags(flags)?;
+        if wide && (field == FieldSize::Gf8 || flags & FLAG_SET == 0) {
+            return Err(SsssErr::MalformedShard("version 2 is only for splits in wider fields".into()));
+        }
+        let shard_number = reader.number(wide)?;
+
+        let set = if flags & FLAG_SET != 0 {
+            Some(ShardSet {
+                id: u32::from_be_bytes(reader.array()?),
+                threshold: reader.number(wide)?,
+                num_shards: reader.number(wide)?,
+                checksum: flags & FLAG_CHECKSUM != 0,
+                field,
+            })
+        } else {
+            None
+        };
+
+        let len = u32::from_be_bytes(reader.array()?) as usize;
+        let data = reader.take(len)?.to_vec();
+        if !reader.0.is_empty() {
+            return Err(SsssErr::MalformedShard(format!("{} bytes after the data", reader.0.len())));
+        }
 
         Ok(SsssShard {
-            shard_poolsize: None,
-            shard_number: split[0].parse().map_err::<String, _>(|_| PARSE_ERR.into())?,
-            data: base64_decode(split[1]).map_err(|x| x.to_string())?,
+            shard_poolsize: set.map(|s| s.num_shards),
+            shard_number,
+            data,
+            version: if set.is_some() { FORMAT_VERSION } else { 0 },
+            set,
         })
     }
 }
 
+/// Reads a binary shard front to back
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SsssErr> {
+        if self.0.len() < len {
+            return Err(SsssErr::MalformedShard("binary shard is truncated".into()));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, SsssErr> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SsssErr> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    /// A shard number or count: four bytes from version 2, one byte before
+    fn number(&mut self, wide: bool) -> Result<u32, SsssErr> {
+        if wide { Ok(u32::from_be_bytes(self.array()?)) } else { Ok(self.byte()? as u32) }
+    }
+}
+
+impl fmt::Display for SsssShard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_with(&Base64))
+    }
+}
+
+fn parse_field<T: str::FromStr>(field: &str, name: &str) -> Result<T, SsssErr> {
+    field.parse().map_err(|_| SsssErr::MalformedShard(format!("invalid {name} `{field}`")))
+}
+
+impl str::FromStr for SsssShard {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s, &Base64)
+    }
+}
+
+/// Parses a shard, its data field in `encoding`, ignoring surrounding whitespace
+fn parse(s: &str, encoding: &dyn Encoding) -> Result<SsssShard, SsssErr> {
+    let s = s.trim();
+    if let Some(versioned) = s.strip_prefix('v') {
+        return parse_versioned(versioned, encoding);
+    }
+
+    // The data may hold dashes in some encodings
+    let Some((number, data)) = s.split_once('-') else {
+        return Err(SsssErr::MalformedShard("expected `<number>-<data>`".into()));
+    };
+
+    let shard_number = parse_field(number, "shard number")?;
+    check_fits(FieldSize::Gf8, shard_number, "shard number")?;
+
+    Ok(SsssShard {
+        shard_poolsize: None,
+        shard_number,
+        data: encoding.decode(data)?,
+        version: 0,
+        set: None,
+    })
+}
+
+/// Rejects a number the shard's field has no element for, so it can't be truncated on the way out
+fn check_fits(field: FieldSize, value: u32, name: &str) -> Result<(), SsssErr> {
+    if value > field.max_shards() {
+        return Err(SsssErr::MalformedShard(format!("{name} {value} does not fit {field}")));
+    }
+    Ok(())
+}
+
+/// Number of `-`-separated fields that follow the version of a versioned shard
+pub(crate) fn versioned_fields(version: u8) -> Result<usize, SsssErr> {
+    match version {
+        1 => Ok(5),
+        FORMAT_VERSION => Ok(6),
+        _ => Err(SsssErr::UnsupportedVersion(version)),
+    }
+}
+
+/// Parses a versioned shard, the leading `v` already stripped
+///
+/// Version 1 is `<version>-<set id>-<threshold>-<total>-<number>-<data>`, version 2 adds a hex
+/// `<flags>` field after the set id.
+fn parse_versioned(s: &str, encoding: &dyn Encoding) -> Result<SsssShard, SsssErr> {
+    let (version, rest) = s.split_once('-')
+        .ok_or_else(|| SsssErr::MalformedShard("missing format version".into()))?;
+
+    let version: u8 = parse_field(version, "version")?;
+    let num_fields = versioned_fields(version)?;
+
+    let mut split: Vec<&str> = rest.splitn(num_fields, '-').collect();
+    if split.len() != num_fields {
+        return Err(SsssErr::MalformedShard(format!("expected {} fields for version {version}", num_fields + 1)));
+    }
+
+    let id = u32::from_str_radix(split[0], 16)
+        .map_err(|_| SsssErr::MalformedShard(format!("invalid set id `{}`", split[0])))?;
+    let flags = if version == 1 { 0 } else {
+        let flags = u8::from_str_radix(split.remove(1), 16)
+            .map_err(|_| SsssErr::MalformedShard("invalid flags".into()))?;
+        if flags & !(FLAG_CHECKSUM | FIELD_FLAGS) != 0 {
+            return Err(SsssErr::MalformedShard(format!("unknown flags {flags:02x}")));
+        }
+        flags
+    };
+
+    let set = ShardSet {
+        id,
+        threshold: parse_field(split[1], "threshold")?,
+        num_shards: parse_field(split[2], "total")?,
+        checksum: flags & FLAG_CHECKSUM != 0,
+        field: field_from_flags(flags)?,
+    };
+    let shard_number = parse_field(split[3], "shard number")?;
+    check_fits(set.field, set.threshold, "threshold")?;
+    check_fits(set.field, set.num_shards, "total")?;
+    check_fits(set.field, shard_number, "shard number")?;
+
+    Ok(SsssShard {
+        shard_poolsize: Some(set.num_shards),
+        shard_number,
+        data: encoding.decode(split[4])?,
+        version,
+        set: Some(set),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,9 +389,23 @@ mod tests {
             shard_poolsize: Some(222),
             shard_number: 13,
             data: vec![65, 66, 67, 65],
+            version: 0,
+            set: None,
         }
     }
 
+    const VERSIONED_STRING: &str = "v2-00c0ffee-01-3-12-04-QUJDQQ==";
+    fn versioned_shard() -> SsssShard {
+        let set = ShardSet { id: 0xc0ffee, threshold: 3, num_shards: 12, checksum: true, field: FieldSize::Gf8 };
+        SsssShard::new(set, 4, vec![65, 66, 67, 65])
+    }
+
+    const WIDE_STRING: &str = "v2-00c0ffee-02-3-300-004-QUJDQQ==";
+    fn wide_shard() -> SsssShard {
+        let set = ShardSet { id: 0xc0ffee, threshold: 3, num_shards: 300, checksum: false, field: FieldSize::Gf16 };
+        SsssShard::new(set, 4, vec![65, 66, 67, 65])
+    }
+
     #[test]
     fn formatting() {
         let formatted = format!("{}", example_shard());
@@ -90,5 +423,153 @@ mod tests {
 
         // No need to ascertain shard_poolsize magnitude during parse
         assert_eq!(None, shard.shard_poolsize);
+        assert_eq!(0, shard.version());
+        assert_eq!(None, shard.threshold());
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        let padded = format!(" {VERSIONED_STRING}\n");
+
+        assert_eq!(Ok(versioned_shard().data), padded.parse::<SsssShard>().map(|s| s.data));
+        assert_eq!(Ok(versioned_shard().set), SsssShard::parse_with(&padded, &Base64).map(|s| s.set));
+    }
+
+    #[test]
+    fn versioned_formatting() {
+        assert_eq!(VERSIONED_STRING, versioned_shard().to_string());
+    }
+
+    #[test]
+    fn versioned_parsing() {
+        let shard: SsssShard = VERSIONED_STRING.parse().unwrap();
+
+        let s = versioned_shard();
+        assert_eq!(s.shard_number, shard.shard_number);
+        assert_eq!(s.data, shard.data);
+        assert_eq!(s.set, shard.set);
+        assert_eq!(FORMAT_VERSION, shard.version());
+        assert_eq!(Some(0xc0ffee), shard.set_id());
+        assert_eq!(Some(3), shard.threshold());
+        assert_eq!(Some(12), shard.num_shards());
+        assert!(shard.has_checksum());
+    }
+
+    #[test]
+    fn wide_field_round_trip() {
+        assert_eq!(WIDE_STRING, wide_shard().to_string());
+
+        let shard: SsssShard = WIDE_STRING.parse().unwrap();
+        assert_eq!(FieldSize::Gf16, shard.field());
+        assert_eq!(Some(300), shard.num_shards());
+        assert!(!shard.has_checksum());
+        assert_eq!(FieldSize::Gf8, versioned_shard().field());
+        assert!(matches!("v2-00c0ffee-06-3-300-004-QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+    }
+
+    #[test]
+    fn narrow_field_numbers_fit_a_byte() {
+        for text in ["256-QUJDQQ==", "v2-00c0ffee-00-3-12-256-QUJDQQ==", "v2-00c0ffee-00-3-300-04-QUJDQQ==", "v1-00c0ffee-300-12-04-QUJDQQ=="] {
+            assert!(matches!(text.parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))), "{text}");
+        }
+
+        let shard: SsssShard = "255-QUJDQQ==".parse().unwrap();
+        assert_eq!(255, shard.to_bytes()[6]);
+    }
+
+    #[test]
+    fn version_one_parsing() {
+        let shard: SsssShard = "v1-00c0ffee-3-12-04-QUJDQQ==".parse().unwrap();
+
+        assert_eq!(1, shard.version());
+        assert_eq!(Some(3), shard.threshold());
+        assert!(!shard.has_checksum());
+        assert_eq!(vec![65, 66, 67, 65], shard.data);
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        use crate::encoding::{Base32, Base64Url, Bech32m, Hex, Words, ZBase32};
+
+        let encodings: [&dyn Encoding; 7] = [&Base64, &Base64Url, &Base32, &ZBase32, &Hex, &Bech32m::default(), &Words];
+        let shard = SsssShard { data: vec![0xfb, 0xff, 0xfe, 1, 2], ..versioned_shard() };
+        for encoding in encodings {
+            let text = shard.to_string_with(encoding);
+            let parsed = SsssShard::parse_with(&text, encoding).unwrap();
+
+            assert!(text.starts_with("v2-00c0ffee-01-3-12-04-"));
+            assert_eq!(shard.data, parsed.data);
+            assert_eq!(shard.set, parsed.set);
+
+            let legacy = SsssShard { data: shard.data.clone(), ..example_shard() };
+            let parsed = SsssShard::parse_with(&legacy.to_string_with(encoding), encoding).unwrap();
+            assert_eq!(legacy.data, parsed.data);
+            assert_eq!(13, parsed.number());
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        for shard in [versioned_shard(), example_shard(), wide_shard()] {
+            let bytes = shard.to_bytes();
+            let parsed = SsssShard::from_bytes(&bytes).unwrap();
+
+            assert_eq!(shard.shard_number, parsed.shard_number);
+            assert_eq!(shard.data, parsed.data);
+            assert_eq!(shard.set, parsed.set);
+        }
+    }
+
+    #[test]
+    fn binary_layout() {
+        let bytes = versioned_shard().to_bytes();
+
+        assert_eq!(b"SSSS\x01\x81\x04\x00\xc0\xff\xee\x03\x0c\x00\x00\x00\x04ABCA", &bytes[..bytes.len() - 4]);
+        assert_eq!(crc32(&bytes[..bytes.len() - 4]).to_be_bytes(), bytes[bytes.len() - 4..]);
+        assert_eq!(b"SSSS\x01\x00\x0d\x00\x00\x00\x04ABCA".len() + 4, example_shard().to_bytes().len());
+
+        let bytes = wide_shard().to_bytes();
+        assert_eq!(
+            b"SSSS\x02\x82\x00\x00\x00\x04\x00\xc0\xff\xee\x00\x00\x00\x03\x00\x00\x01\x2c\x00\x00\x00\x04ABCA",
+            &bytes[..bytes.len() - 4]
+        );
+    }
+
+    #[test]
+    fn binary_corruption_is_detected() {
+        let bytes = versioned_shard().to_bytes();
+
+        for i in BINARY_MAGIC.len()..bytes.len() {
+            for bit in 0..8 {
+                let mut corrupted = bytes.clone();
+                corrupted[i] ^= 1 << bit;
+                assert_eq!(Err(SsssErr::InvalidChecksum), SsssShard::from_bytes(&corrupted).map(|s| s.data));
+            }
+        }
+    }
+
+    #[test]
+    fn binary_errors() {
+        let bytes = versioned_shard().to_bytes();
+        let with_crc = |body: &[u8]| [body, &crc32(body).to_be_bytes()].concat();
+
+        assert!(matches!(SsssShard::from_bytes(b"SSSX"), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(SsssShard::from_bytes(&bytes[..6]), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(SsssShard::from_bytes(&with_crc(&bytes[..bytes.len() - 5])), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(SsssShard::from_bytes(&with_crc(&[&bytes[..bytes.len() - 4], b"x"].concat())), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(SsssShard::from_bytes(&with_crc(b"SSSS\x01\x02\x01")), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(SsssShard::from_bytes(&with_crc(b"SSSS\x02\x80\x00")), Err(SsssErr::MalformedShard(_))));
+        assert_eq!(Err(SsssErr::UnsupportedVersion(3)), SsssShard::from_bytes(&with_crc(b"SSSS\x03")).map(|s| s.data));
+    }
+
+    #[test]
+    fn parsing_errors() {
+        assert!(matches!("013QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("x13-QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+        assert_eq!(Err(SsssErr::InvalidBase64 { position: 2 }), "013-QU*DQQ==".parse::<SsssShard>().map(|s| s.data));
+        assert!(matches!("v2-00c0ffee-00-3-04-QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("v2-c0ffeeeee-00-3-12-04-QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!("v2-00c0ffee-80-3-12-04-QUJDQQ==".parse::<SsssShard>(), Err(SsssErr::MalformedShard(_))));
+        assert_eq!(Err(SsssErr::UnsupportedVersion(9)), "v9-00c0ffee-00-3-12-04-QUJDQQ==".parse::<SsssShard>().map(|s| s.data));
     }
 }
@@ -0,0 +1,299 @@
+//! Krawczyk's "secret sharing made short"
+//!
+//! The secret is encrypted under a random key with ChaCha20-Poly1305. The ciphertext is spread
+//! over the shards with Rabin's information dispersal: every group of `threshold` bytes is read
+//! as the coefficients of a polynomial, and each shard receives its value at the shard number.
+//! Only the key is Shamir-shared. A shard thus holds about `len / threshold` bytes instead of
+//! `len`, while `threshold` shards still restore the secret and fewer reveal nothing.
+//!
+//! The dispersal works in GF(2^8), so a split has at most 255 shards.
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::err::{Result, SsssErr};
+use crate::hybrid::{decrypt, encrypt, KEY_LEN};
+use crate::math::{FieldSize, GfPoly, GF};
+use crate::{split_bytes, Reconstructor, ShamirScheme};
+use rand::{Rng, RngExt};
+use std::collections::HashSet;
+use std::{fmt, str};
+
+/// Prefix identifying the textual form of an [`SsmsShard`]
+const PREFIX: &str = "m1";
+
+/// Options for a split in which shards are about `1 / threshold` the size of the secret
+pub struct SsmsScheme {
+    threshold: u32,
+    num_shards: u32,
+}
+
+impl SsmsScheme {
+    /// A split into `num_shards` shards, at most 255
+    #[must_use]
+    pub fn new(threshold: u32, num_shards: u32) -> Self {
+        SsmsScheme { threshold, num_shards }
+    }
+
+    /// The key is shared in GF(2^8) too, which also limits the number of shards
+    fn key_scheme(&self) -> ShamirScheme {
+        ShamirScheme::new(self.threshold, self.num_shards).with_field(FieldSize::Gf8)
+    }
+}
+
+/// One custodian's share of an [`SsmsScheme`] split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsmsShard {
+    set_id: u32,
+    threshold: u32,
+    num_shards: u32,
+    number: u32,
+    /// Length of the dispersed ciphertext, before padding it to a multiple of the threshold
+    ciphertext_len: usize,
+    key_share: Vec<u8>,
+    fragment: Vec<u8>,
+}
+
+impl SsmsShard {
+    pub fn number(&self) -> u32 { self.number }
+    pub fn set_id(&self) -> u32 { self.set_id }
+    pub fn threshold(&self) -> u32 { self.threshold }
+    pub fn num_shards(&self) -> u32 { self.num_shards }
+
+    /// Number of payload bytes the shard holds
+    pub fn len(&self) -> usize { self.key_share.len() + self.fragment.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+/// Encrypts `secret`, disperses the ciphertext and shares the key
+pub fn encode(options: &SsmsScheme, secret: &[u8]) -> Result<Vec<SsmsShard>> {
+    let key_scheme = options.key_scheme();
+    key_scheme.validate(secret)?;
+
+    let mut key = [0u8; KEY_LEN];
+    rand::rng().fill_bytes(&mut key);
+    let ciphertext = encrypt(&key, secret)?;
+
+    let set_id = rand::rng().random();
+    let fragments = disperse(&ciphertext, options.threshold, options.num_shards);
+
+    Ok(split_bytes(&key_scheme, &key)
+        .into_iter()
+        .zip(fragments)
+        .zip(1..)
+        .map(|((key_share, fragment), number)| SsmsShard {
+            set_id,
+            threshold: options.threshold,
+            num_shards: options.num_shards,
+            number,
+            ciphertext_len: ciphertext.len(),
+            key_share,
+            fragment,
+        })
+        .collect())
+}
+
+/// Restores the key and the ciphertext from the shards, and decrypts
+pub fn decode(shards: &[SsmsShard]) -> Result<Vec<u8>> {
+    validate_shards(shards)?;
+
+    let shards = &shards[..shards[0].threshold as usize];
+    let x: Vec<u32> = shards.iter().map(|s| s.number).collect();
+
+    let key_shares: Vec<&[u8]> = shards.iter().map(|s| s.key_share.as_slice()).collect();
+    let key = Reconstructor::new(&x)?.reconstruct(&key_shares)?;
+
+    let fragments: Vec<&[u8]> = shards.iter().map(|s| s.fragment.as_slice()).collect();
+    let mut ciphertext = recover(&x, &fragments)?;
+    ciphertext.truncate(shards[0].ciphertext_len);
+
+    decrypt(&key, &ciphertext)
+}
+
+fn validate_shards(shards: &[SsmsShard]) -> Result<()> {
+    let Some(first) = shards.first() else {
+        return Err(SsssErr::InsufficientShards { needed: 1, got: 0 });
+    };
+    if shards.len() < first.threshold as usize {
+        return Err(SsssErr::InsufficientShards { needed: first.threshold as usize, got: shards.len() });
+    }
+
+    let mut seen = HashSet::new();
+    for s in shards {
+        if (s.set_id, s.threshold, s.num_shards, s.ciphertext_len)
+            != (first.set_id, first.threshold, first.num_shards, first.ciphertext_len)
+        {
+            return Err(SsssErr::MixedShardSets);
+        }
+        if s.number == 0 || s.number > s.num_shards || s.number > FieldSize::Gf8.max_shards() {
+            return Err(SsssErr::InvalidShardNumber(s.number));
+        }
+        if !seen.insert(s.number) {
+            return Err(SsssErr::DuplicateShard(s.number));
+        }
+
+        if s.key_share.len() != KEY_LEN || s.fragment.len() != first.fragment.len() {
+            return Err(SsssErr::MismatchedShardLength { expected: first.len(), found: s.len() });
+        }
+    }
+
+    Ok(())
+}
+
+/// Rabin's dispersal: shard `x` receives, per group of `threshold` bytes, the value at `x` of the
+/// polynomial with those bytes as coefficients
+fn disperse(data: &[u8], threshold: u32, num_shards: u32) -> Vec<Vec<u8>> {
+    let groups: Vec<GfPoly> = data.chunks(threshold as usize).map(GfPoly::new).collect();
+
+    (1..=num_shards as u8)
+        .map(|x| groups.iter().map(|g| g.apply_x(x).value() as u8).collect())
+        .collect()
+}
+
+/// Inverse of [`disperse`], given the fragments of `threshold` distinct shards, whose numbers
+/// are elements of GF(2^8)
+fn recover(x: &[u32], fragments: &[&[u8]]) -> Result<Vec<u8>> {
+    let x: Vec<u8> = x.iter().map(|&x| x as u8).collect();
+    let basis = GfPoly::lagrange_basis(&x)?;
+    let mut data = Vec::with_capacity(fragments[0].len() * x.len());
+
+    for i in 0..fragments[0].len() {
+        for j in 0..x.len() {
+            let coefficient = basis
+                .iter()
+                .zip(fragments)
+                .fold(GF::new(0), |acc, (l, f)| acc + l.coefficients()[j] * GF::new(f[i]));
+            data.push(coefficient.value() as u8);
+        }
+    }
+
+    Ok(data)
+}
+
+impl fmt::Display for SsmsShard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let payload = [self.key_share.as_slice(), self.fragment.as_slice()].concat();
+        let width = self.num_shards.to_string().len();
+
+        write!(
+            f,
+            "{PREFIX}-{:08x}-{}-{}-{:0width$}-{}-{}",
+            self.set_id,
+            self.threshold,
+            self.num_shards,
+            self.number,
+            self.ciphertext_len,
+            base64_encode(&payload).map_err(|_| fmt::Error)?,
+            width = width
+        )
+    }
+}
+
+impl str::FromStr for SsmsShard {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split: Vec<&str> = s.splitn(7, '-').collect();
+        if split.len() != 7 || split[0] != PREFIX {
+            return Err(SsssErr::MalformedShard(format!(
+                "expected `{PREFIX}-<set id>-<threshold>-<total>-<number>-<length>-<data>`"
+            )));
+        }
+
+        let malformed = |name: &str| SsssErr::MalformedShard(format!("invalid {name}"));
+        let mut payload = base64_decode(split[6])?;
+        if payload.len() < KEY_LEN {
+            return Err(malformed("data"));
+        }
+        let fragment = payload.split_off(KEY_LEN);
+
+        Ok(SsmsShard {
+            set_id: u32::from_str_radix(split[1], 16).map_err(|_| malformed("set id"))?,
+            threshold: split[2].parse().map_err(|_| malformed("threshold"))?,
+            num_shards: split[3].parse().map_err(|_| malformed("total"))?,
+            number: split[4].parse().map_err(|_| malformed("shard number"))?,
+            ciphertext_len: split[5].parse().map_err(|_| malformed("length"))?,
+            key_share: payload,
+            fragment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_secret(len: usize) -> Vec<u8> {
+        let mut secret = vec![0; len];
+        rand::rng().fill_bytes(&mut secret);
+        secret
+    }
+
+    #[test]
+    fn end_to_end() {
+        let secret = random_secret(1000);
+        let shards = encode(&SsmsScheme::new(3, 5), &secret).unwrap();
+
+        assert_eq!(secret, decode(&shards[0..3]).unwrap());
+        assert_eq!(secret, decode(&shards[2..5]).unwrap());
+        assert_eq!(secret, decode(&[shards[4].clone(), shards[0].clone(), shards[2].clone()]).unwrap());
+    }
+
+    #[test]
+    fn shards_are_short() {
+        let secret = random_secret(30_000);
+
+        for threshold in [1, 2, 3, 7] {
+            let shards = encode(&SsmsScheme::new(threshold, 8), &secret).unwrap();
+            let expected = secret.len() / threshold as usize;
+
+            // Only the key share, nonce and tag add a constant overhead
+            assert!(shards.iter().all(|s| s.len() >= expected && s.len() <= expected + 64));
+        }
+    }
+
+    #[test]
+    fn too_few_shards() {
+        let shards = encode(&SsmsScheme::new(3, 5), b"secret").unwrap();
+
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 3, got: 2 }), decode(&shards[0..2]));
+    }
+
+    #[test]
+    fn tampered_fragment() {
+        let mut shards = encode(&SsmsScheme::new(2, 3), b"some longer secret").unwrap();
+        shards[1].fragment[0] ^= 1;
+
+        assert_eq!(Err(SsssErr::DecryptionFailed), decode(&shards[0..2]));
+    }
+
+    #[test]
+    fn mixed_sets() {
+        let shards = encode(&SsmsScheme::new(2, 3), b"secret").unwrap();
+        let other = encode(&SsmsScheme::new(2, 3), b"secret").unwrap();
+
+        assert_eq!(Err(SsssErr::MixedShardSets), decode(&[shards[0].clone(), other[1].clone()]));
+    }
+
+    #[test]
+    fn at_most_255_shards() {
+        assert_eq!(
+            Err(SsssErr::TooManyShards { field: FieldSize::Gf8, num_shards: 256 }),
+            encode(&SsmsScheme::new(2, 256), b"secret").map(|_| ())
+        );
+        assert_eq!(255, encode(&SsmsScheme::new(2, 255), b"secret").unwrap()[254].number());
+
+        let shards = encode(&SsmsScheme::new(2, 3), b"secret").unwrap();
+        let forged = SsmsShard { number: 256, num_shards: 300, ..shards[0].clone() };
+        let shards = [SsmsShard { num_shards: 300, ..shards[1].clone() }, forged];
+        assert_eq!(Err(SsssErr::InvalidShardNumber(256)), decode(&shards));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let shards = encode(&SsmsScheme::new(2, 3), b"secret").unwrap();
+        let text = shards[1].to_string();
+
+        assert!(text.starts_with("m1-"));
+        assert_eq!(shards[1], text.parse().unwrap());
+        assert!(matches!("v2-00000000-00-2-3-1-AAAA".parse::<SsmsShard>(), Err(SsssErr::MalformedShard(_))));
+    }
+}
@@ -0,0 +1,306 @@
+//! Sharding and merging of arbitrarily large secrets in constant memory
+//!
+//! The secret is processed in blocks of [`BLOCK_LEN`] bytes, or with the `parallel` feature of
+//! that many per thread, so each block keeps all threads busy. Every output receives exactly the
+//! text that `SsssShard`'s `Display` would produce for the whole secret, followed by a newline,
+//! so streamed shards and in-memory shards are interchangeable.
+//!
+//! Only splits over GF(2^8) can be streamed, as the wider fields pad the whole secret.
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::err::{Result, SsssErr};
+use crate::math::FieldSize;
+use crate::shard::versioned_fields;
+use crate::{integrity, split_bytes, validate_shards, Reconstructor, ShamirScheme, SsssShard, CHUNK_LEN};
+use sha2::Digest;
+use std::io::{Read, Write};
+
+/// Number of secret bytes processed at once per thread; a multiple of 3, so the base64 of
+/// consecutive blocks concatenates without padding in between, and of the chunks split across
+/// threads
+pub const BLOCK_LEN: usize = 3 * CHUNK_LEN;
+
+#[cfg(not(feature = "parallel"))]
+fn block_len() -> usize {
+    BLOCK_LEN
+}
+
+#[cfg(feature = "parallel")]
+fn block_len() -> usize {
+    BLOCK_LEN * rayon::current_num_threads()
+}
+
+/// Longest shard header accepted while looking for the start of the data
+const MAX_HEADER_LEN: usize = 64;
+
+/// Shards everything read from `input`, writing shard `i + 1` to `outputs[i]`
+pub fn encode_stream<R: Read, W: Write>(options: &ShamirScheme, mut input: R, outputs: &mut [W]) -> Result<()> {
+    if outputs.len() != options.num_shards as usize {
+        return Err(SsssErr::WrongNumberOfStreams { expected: options.num_shards as usize, got: outputs.len() });
+    }
+
+    if options.field() != FieldSize::Gf8 {
+        return Err(SsssErr::UnsupportedField(options.field()));
+    }
+
+    let mut block = vec![0u8; block_len()];
+    let mut len = read_block(&mut input, &mut block)?;
+    options.validate(&block[..len])?;
+
+    let set = options.new_set();
+    let mut hasher = integrity::hasher(set.id);
+    for (i, output) in outputs.iter_mut().enumerate() {
+        output.write_all(SsssShard::new(set, i as u32 + 1, vec![]).header().as_bytes())?;
+    }
+
+    // Shard bytes not yet written, as base64 can only be written three bytes at a time
+    let mut pending = vec![Vec::new(); outputs.len()];
+    while len > 0 {
+        if set.checksum {
+            hasher.update(&block[..len]);
+        }
+        append_shares(options, &block[..len], &mut pending);
+        write_base64(outputs, &mut pending, false)?;
+
+        len = read_block(&mut input, &mut block)?;
+    }
+
+    if set.checksum {
+        append_shares(options, &hasher.finalize(), &mut pending);
+    }
+    write_base64(outputs, &mut pending, true)?;
+
+    for output in outputs.iter_mut() {
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Restores the secret from shards read from `inputs`, writing it to `output`
+///
+/// The secret is written as it is restored. With an integrity check, a mismatch can therefore
+/// only be reported once everything has been written, and the output must then be discarded.
+pub fn decode_stream<R: Read, W: Write>(inputs: &mut [R], mut output: W) -> Result<()> {
+    let headers = inputs.iter_mut().map(read_header).collect::<Result<Vec<_>>>()?;
+    validate_shards(&headers)?;
+    if headers[0].field() != FieldSize::Gf8 {
+        return Err(SsssErr::UnsupportedField(headers[0].field()));
+    }
+
+    let set = headers[0].set();
+    let checksum = set.is_some_and(|s| s.checksum);
+    let mut hasher = integrity::hasher(set.map_or(0, |s| s.id));
+    let x: Vec<u32> = headers.iter().map(|s| s.num()).collect();
+    let reconstructor = Reconstructor::new(&x)?;
+
+    let mut block = vec![0u8; block_len() / 3 * 4];
+    // With an integrity check the trailing digest must not be written, so hold it back
+    let mut held = Vec::new();
+    loop {
+        let mut columns = Vec::with_capacity(inputs.len());
+        for input in inputs.iter_mut() {
+            let len = read_block(input, &mut block)?;
+            let text = std::str::from_utf8(&block[..len])
+                .map_err(|_| SsssErr::MalformedShard("shard data is not text".into()))?;
+            columns.push(base64_decode(text.trim_end())?);
+        }
+
+        let restored = reconstructor.reconstruct(&columns)?;
+        if restored.is_empty() {
+            break;
+        }
+        held.extend(restored);
+
+        let keep = if checksum { integrity::DIGEST_LEN.min(held.len()) } else { 0 };
+        let ready = held.len() - keep;
+        hasher.update(&held[..ready]);
+        output.write_all(&held[..ready])?;
+        held.drain(..ready);
+    }
+    output.flush()?;
+
+    if checksum && !integrity::digests_match(&held, &hasher.finalize()) {
+        return Err(SsssErr::IntegrityCheckFailed);
+    }
+    Ok(())
+}
+
+fn append_shares(options: &ShamirScheme, bytes: &[u8], pending: &mut [Vec<u8>]) {
+    for (pending, shares) in pending.iter_mut().zip(split_bytes(options, bytes)) {
+        pending.extend(shares);
+    }
+}
+
+/// Writes the pending shard bytes as base64; all of them when `last`, else a multiple of three
+fn write_base64<W: Write>(outputs: &mut [W], pending: &mut [Vec<u8>], last: bool) -> Result<()> {
+    for (output, pending) in outputs.iter_mut().zip(pending.iter_mut()) {
+        let len = if last { pending.len() } else { pending.len() / 3 * 3 };
+        output.write_all(base64_encode(&pending[..len])?.as_bytes())?;
+        pending.drain(..len);
+    }
+    Ok(())
+}
+
+/// Fills `block` as far as the input allows, returning the number of bytes read
+fn read_block<R: Read>(input: &mut R, block: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < block.len() {
+        match input.read(&mut block[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(len)
+}
+
+/// Reads a shard header up to and including the `-` that precedes the data
+fn read_header<R: Read>(input: &mut R) -> Result<SsssShard> {
+    let mut header = Vec::new();
+    let mut dashes_needed = None;
+    let mut dashes = 0;
+
+    while Some(dashes) != dashes_needed {
+        if header.len() >= MAX_HEADER_LEN {
+            return Err(SsssErr::MalformedShard("shard header is too long".into()));
+        }
+
+        let mut byte = [0u8];
+        if read_block(input, &mut byte)? == 0 {
+            return Err(SsssErr::MalformedShard("unexpected end of shard header".into()));
+        }
+        header.push(byte[0]);
+
+        if byte[0] == b'-' {
+            dashes += 1;
+            if dashes_needed.is_none() {
+                dashes_needed = Some(match header.strip_prefix(b"v") {
+                    Some(version) => {
+                        let version = std::str::from_utf8(&version[..version.len() - 1])
+                            .ok()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(|| SsssErr::MalformedShard("invalid version".into()))?;
+                        versioned_fields(version)?
+                    }
+                    None => 1,
+                });
+            }
+        }
+    }
+
+    String::from_utf8(header)
+        .map_err(|_| SsssErr::MalformedShard("shard header is not text".into()))?
+        .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::math::GF;
+
+    fn split(options: &ShamirScheme, secret: &[u8]) -> Vec<Vec<u8>> {
+        let mut outputs = vec![Vec::new(); options.num_shards as usize];
+        encode_stream(options, secret, &mut outputs).unwrap();
+        outputs
+    }
+
+    fn merge(shards: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let mut inputs: Vec<&[u8]> = shards.iter().map(|s| s.as_slice()).collect();
+        let mut output = Vec::new();
+        decode_stream(&mut inputs, &mut output)?;
+        Ok(output)
+    }
+
+    fn random_secret(len: usize) -> Vec<u8> {
+        let mut secret = vec![0; len];
+        rand::rng().fill_bytes(&mut secret);
+        secret
+    }
+
+    #[test]
+    fn round_trip_over_several_blocks() {
+        let secret = random_secret(2 * block_len() + 1000);
+        let shards = split(&ShamirScheme::new(3, 5), &secret);
+
+        assert_eq!(secret, merge(&shards[1..4]).unwrap());
+    }
+
+    #[test]
+    fn blocks_span_several_chunks() {
+        assert_eq!(0, block_len() % (3 * CHUNK_LEN));
+        #[cfg(feature = "parallel")]
+        assert!(block_len() / CHUNK_LEN >= rayon::current_num_threads());
+
+        // Restore every byte, across chunk and block boundaries, one at a time
+        let secret = random_secret(block_len() + CHUNK_LEN + 5);
+        let shards: Vec<SsssShard> = split(&ShamirScheme::new(3, 5), &secret)
+            .iter()
+            .map(|s| std::str::from_utf8(s).unwrap().trim_end().parse().unwrap())
+            .collect();
+        let x = [GF::new(1), GF::new(2), GF::new(4)];
+        let weights: Vec<GF> = (0..3)
+            .map(|j| (0..3).filter(|&m| m != j).fold(GF::new(1), |w, m| w * x[m] * (x[m] - x[j]).invert().unwrap()))
+            .collect();
+        for (i, &byte) in secret.iter().enumerate() {
+            let restored = [0, 1, 3].iter().zip(&weights).fold(GF::new(0), |sum, (&s, &w)| sum + w * GF::new(shards[s].data()[i]));
+            assert_eq!(GF::new(byte), restored, "byte {i}");
+        }
+    }
+
+    #[test]
+    fn output_is_interchangeable_with_shard_text() {
+        let secret = random_secret(block_len() + 2);
+        let shards = split(&ShamirScheme::new(2, 3).with_integrity_check(), &secret);
+
+        let parsed: Vec<SsssShard> = shards
+            .iter()
+            .map(|s| std::str::from_utf8(s).unwrap().trim_end().parse().unwrap())
+            .collect();
+        assert_eq!(secret, crate::decode(&parsed).unwrap());
+
+        let text: Vec<Vec<u8>> = crate::encode(&ShamirScheme::new(2, 3), &secret)
+            .unwrap()
+            .iter()
+            .map(|s| format!("{s}\n").into_bytes())
+            .collect();
+        assert_eq!(secret, merge(&text[0..2]).unwrap());
+    }
+
+    #[test]
+    fn integrity_check() {
+        let secret = random_secret(block_len() * 2);
+        let mut shards = split(&ShamirScheme::new(2, 3).with_integrity_check(), &secret);
+
+        assert_eq!(secret, merge(&shards[0..2]).unwrap());
+
+        let position = shards[1].len() - 10;
+        shards[1][position] = if shards[1][position] == b'A' { b'B' } else { b'A' };
+        assert_eq!(Err(SsssErr::IntegrityCheckFailed), merge(&shards[0..2]));
+    }
+
+    #[test]
+    fn errors() {
+        let shards = split(&ShamirScheme::new(3, 5), b"secret");
+        let mut outputs = vec![Vec::new(); 2];
+
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 3, got: 2 }), merge(&shards[0..2]));
+        assert_eq!(
+            Err(SsssErr::WrongNumberOfStreams { expected: 5, got: 2 }),
+            encode_stream(&ShamirScheme::new(3, 5), b"secret".as_slice(), &mut outputs)
+        );
+        assert_eq!(Err(SsssErr::EmptySecret), encode_stream(&ShamirScheme::new(1, 2), b"".as_slice(), &mut outputs));
+        assert!(matches!(merge(&[b"v2-0000".to_vec()]), Err(SsssErr::MalformedShard(_))));
+    }
+
+    #[test]
+    fn mismatched_lengths() {
+        // Six bytes of secret encode without padding, so more data can be appended
+        let mut shards = split(&ShamirScheme::new(2, 2), b"secret");
+        shards[1] = format!("{}AAAA\n", std::str::from_utf8(&shards[1]).unwrap().trim_end()).into_bytes();
+
+        assert_eq!(Err(SsssErr::MismatchedShardLength { expected: 6, found: 9 }), merge(&shards));
+    }
+}
@@ -0,0 +1,49 @@
+//! Feldman's verifiable secret sharing
+//!
+//! For every chunk the dealer publishes `C_j = a_j * G` for each polynomial coefficient `a_j`.
+//! The holder of shard `x` with value `y` checks that `y * G` equals the sum of `x^j * C_j`.
+//! Note that `C_0` is `secret * G`: a secret with little entropy can be guessed from it, which
+//! [`super::pedersen`] avoids.
+
+use super::{Commitments, Scheme, Shard};
+use crate::math::ScalarPoly;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+/// Commits to the secret polynomial alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feldman {}
+
+pub type FeldmanShard = Shard<Feldman>;
+pub type FeldmanCommitments = Commitments<Feldman>;
+
+impl Scheme for Feldman {
+    const SHARD_TAG: &'static str = "f1";
+    const COMMITMENTS_TAG: &'static str = "fc1";
+    const VALUES_PER_CHUNK: usize = 1;
+
+    fn polynomials(chunk: Scalar, degree: usize) -> Vec<ScalarPoly> {
+        vec![ScalarPoly::random(chunk, degree)]
+    }
+
+    fn commit(scalars: &[Scalar]) -> RistrettoPoint {
+        RistrettoPoint::mul_base(&scalars[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vss::encode;
+    use crate::ShamirScheme;
+
+    #[test]
+    fn constant_commitment_exposes_the_secret() {
+        // The weakness Pedersen's scheme fixes: `C_0` is the same for equal secrets
+        let (first, _): (FeldmanCommitments, _) = encode(&ShamirScheme::new(2, 3), b"secret").unwrap();
+        let (second, _): (FeldmanCommitments, _) = encode(&ShamirScheme::new(2, 3), b"secret").unwrap();
+
+        assert_eq!(first.points[0], second.points[0]);
+        assert_ne!(first.points[1], second.points[1]);
+    }
+}
@@ -0,0 +1,446 @@
+//! Verifiable secret sharing over the prime-order Ristretto255 group
+//!
+//! Unlike the GF(2^8) scheme, every shard can be checked against public commitments to the
+//! polynomial coefficients, so custodians need not trust the dealer. The secret is cut into
+//! 31-byte chunks, each of which fits in a scalar and is shared with its own polynomial. Shard
+//! numbers are scalars as well, so a split may have up to 2^32 - 1 shards, whichever field the
+//! [`ShamirScheme`] names.
+//!
+//! [`feldman`] commitments are computationally hiding only, [`pedersen`] ones reveal nothing.
+//! Both share and verify the same way, through [`encode`], [`verify`] and [`decode`]; a
+//! [`Scheme`] tells how a chunk is shared and committed to.
+
+pub mod feldman;
+pub mod pedersen;
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::err::{Result, SsssErr};
+use crate::math::{interpolate_at_zero, ScalarPoly};
+use crate::ShamirScheme;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::{fmt, str};
+
+/// How a verifiable scheme shares a chunk of the secret and commits to it
+///
+/// The schemes are only markers, the bounds let shards and commitments derive their traits.
+pub trait Scheme: fmt::Debug + Clone + PartialEq + Eq {
+    /// Tag of the shard text, such as `f1`
+    const SHARD_TAG: &'static str;
+    /// Tag of the commitments text, such as `fc1`
+    const COMMITMENTS_TAG: &'static str;
+    /// Scalars a shard holds per chunk, one per polynomial
+    const VALUES_PER_CHUNK: usize;
+
+    /// The polynomials sharing one chunk, the one with the chunk as constant term first
+    fn polynomials(chunk: Scalar, degree: usize) -> Vec<ScalarPoly>;
+
+    /// Commits to one scalar of every polynomial, either a coefficient of each or their values
+    /// in a shard; commitments to values must equal those to coefficients evaluated in the exponent
+    fn commit(scalars: &[Scalar]) -> RistrettoPoint;
+}
+
+/// One custodian's share of a verifiable split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard<S: Scheme> {
+    number: u32,
+    /// [`Scheme::VALUES_PER_CHUNK`] values per chunk, chunk after chunk
+    values: Vec<Scalar>,
+    scheme: PhantomData<S>,
+}
+
+impl<S: Scheme> Shard<S> {
+    pub fn number(&self) -> u32 { self.number }
+
+    fn chunks(&self) -> impl Iterator<Item = &[Scalar]> {
+        self.values.chunks_exact(S::VALUES_PER_CHUNK)
+    }
+}
+
+/// Public commitments to the polynomials of a verifiable split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commitments<S: Scheme> {
+    secret_len: usize,
+    threshold: u32,
+    /// `threshold` points per chunk, chunk after chunk
+    points: Vec<RistrettoPoint>,
+    scheme: PhantomData<S>,
+}
+
+impl<S: Scheme> Commitments<S> {
+    pub fn threshold(&self) -> u32 { self.threshold }
+
+    fn chunk(&self, c: usize) -> &[RistrettoPoint] {
+        let t = self.threshold as usize;
+        &self.points[c * t..(c + 1) * t]
+    }
+}
+
+/// Splits `secret`, returning the commitments to publish and one shard per custodian
+pub fn encode<S: Scheme>(options: &ShamirScheme, secret: &[u8]) -> Result<(Commitments<S>, Vec<Shard<S>>)> {
+    options.validate(secret)?;
+
+    let degree = options.threshold as usize - 1;
+    let polys: Vec<Vec<ScalarPoly>> = secret_to_scalars(secret)
+        .into_iter()
+        .map(|s| S::polynomials(s, degree))
+        .collect();
+
+    let commitments = Commitments {
+        secret_len: secret.len(),
+        threshold: options.threshold,
+        points: polys
+            .iter()
+            .flat_map(|chunk| {
+                (0..=degree).map(move |j| S::commit(&chunk.iter().map(|p| p.coefficients()[j]).collect::<Vec<_>>()))
+            })
+            .collect(),
+        scheme: PhantomData,
+    };
+
+    let shards = (1..=options.num_shards)
+        .map(|x| Shard {
+            number: x,
+            values: polys.iter().flatten().map(|p| p.apply_x(x)).collect(),
+            scheme: PhantomData,
+        })
+        .collect();
+
+    Ok((commitments, shards))
+}
+
+/// Checks a single shard against the published commitments
+///
+/// A shard that does not match is reported as [`SsssErr::InvalidShards`] with its number.
+pub fn verify<S: Scheme>(commitments: &Commitments<S>, shard: &Shard<S>) -> Result<()> {
+    let chunks = num_chunks(commitments.secret_len);
+    let found = shard.values.len() / S::VALUES_PER_CHUNK;
+    if found != chunks {
+        return Err(SsssErr::MismatchedShardLength { expected: chunks, found });
+    }
+    if shard.number == 0 {
+        return Err(SsssErr::InvalidShardNumber(0));
+    }
+
+    let consistent = shard
+        .chunks()
+        .enumerate()
+        .all(|(c, values)| S::commit(values) == commitment_at(commitments.chunk(c), shard.number));
+
+    if consistent { Ok(()) } else { Err(SsssErr::InvalidShards(vec![shard.number])) }
+}
+
+/// Verifies every shard and restores the secret
+///
+/// If any shard does not match the commitments, nothing is restored and the numbers of all
+/// offending shards are returned in [`SsssErr::InvalidShards`].
+pub fn decode<S: Scheme>(commitments: &Commitments<S>, shards: &[Shard<S>]) -> Result<Vec<u8>> {
+    check_shard_numbers(shards.iter().map(|s| s.number))?;
+
+    let mut invalid = Vec::new();
+    for shard in shards {
+        match verify(commitments, shard) {
+            Ok(()) => {}
+            Err(SsssErr::InvalidShards(numbers)) => invalid.extend(numbers),
+            Err(e) => return Err(e),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(SsssErr::InvalidShards(invalid));
+    }
+
+    let threshold = commitments.threshold as usize;
+    if shards.len() < threshold {
+        return Err(SsssErr::InsufficientShards { needed: threshold, got: shards.len() });
+    }
+
+    let shards = &shards[..threshold];
+    let x: Vec<u32> = shards.iter().map(|s| s.number).collect();
+    let scalars = (0..num_chunks(commitments.secret_len))
+        .map(|c| {
+            // The value of the polynomial holding the secret
+            let y: Vec<Scalar> = shards.iter().map(|s| s.values[c * S::VALUES_PER_CHUNK]).collect();
+            interpolate_at_zero(&x, &y)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(scalars_to_secret(&scalars, commitments.secret_len))
+}
+
+impl<S: Scheme> fmt::Display for Shard<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = scalars_to_base64(&self.values).map_err(|_| fmt::Error)?;
+        write!(f, "{}-{}-{}", S::SHARD_TAG, self.number, data)
+    }
+}
+
+impl<S: Scheme> str::FromStr for Shard<S> {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split: Vec<&str> = s.splitn(3, '-').collect();
+        if split.len() != 3 || split[0] != S::SHARD_TAG {
+            return Err(SsssErr::MalformedShard(format!("expected `{}-<number>-<data>`", S::SHARD_TAG)));
+        }
+
+        let values = scalars_from_base64(split[2])?;
+        if !values.len().is_multiple_of(S::VALUES_PER_CHUNK) {
+            return Err(SsssErr::MalformedShard(format!("payload is not a whole number of {} scalars per chunk", S::VALUES_PER_CHUNK)));
+        }
+
+        Ok(Shard {
+            number: parse_number(split[1], SsssErr::MalformedShard)?,
+            values,
+            scheme: PhantomData,
+        })
+    }
+}
+
+impl<S: Scheme> fmt::Display for Commitments<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = points_to_base64(&self.points).map_err(|_| fmt::Error)?;
+        write!(f, "{}-{}-{}-{}", S::COMMITMENTS_TAG, self.secret_len, self.threshold, data)
+    }
+}
+
+impl<S: Scheme> str::FromStr for Commitments<S> {
+    type Err = SsssErr;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split: Vec<&str> = s.splitn(4, '-').collect();
+        if split.len() != 4 || split[0] != S::COMMITMENTS_TAG {
+            return Err(SsssErr::MalformedCommitments(format!(
+                "expected `{}-<length>-<threshold>-<data>`",
+                S::COMMITMENTS_TAG
+            )));
+        }
+
+        let commitments = Commitments {
+            secret_len: parse_number(split[1], SsssErr::MalformedCommitments)?,
+            threshold: parse_number(split[2], SsssErr::MalformedCommitments)?,
+            points: points_from_base64(split[3])?,
+            scheme: PhantomData,
+        };
+
+        // Both numbers are untrusted, so their product may overflow
+        let expected = num_chunks(commitments.secret_len).checked_mul(commitments.threshold as usize);
+        if commitments.threshold == 0 || expected != Some(commitments.points.len()) {
+            return Err(SsssErr::MalformedCommitments("number of points does not match length and threshold".into()));
+        }
+
+        Ok(commitments)
+    }
+}
+
+/// Number of secret bytes that go into one scalar
+const CHUNK_LEN: usize = 31;
+
+/// Number of bytes in a serialized scalar or point
+const ELEMENT_LEN: usize = 32;
+
+fn num_chunks(secret_len: usize) -> usize {
+    secret_len.div_ceil(CHUNK_LEN)
+}
+
+fn secret_to_scalars(secret: &[u8]) -> Vec<Scalar> {
+    secret
+        .chunks(CHUNK_LEN)
+        .map(|chunk| {
+            let mut bytes = [0u8; ELEMENT_LEN];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            Scalar::from_bytes_mod_order(bytes)
+        })
+        .collect()
+}
+
+fn scalars_to_secret(scalars: &[Scalar], secret_len: usize) -> Vec<u8> {
+    let mut secret: Vec<u8> = scalars.iter().flat_map(|s| s.to_bytes()[..CHUNK_LEN].to_vec()).collect();
+    secret.truncate(secret_len);
+    secret
+}
+
+/// Evaluates the polynomial "in the exponent": the sum of `x^j * C_j`
+fn commitment_at(commitments: &[RistrettoPoint], x: u32) -> RistrettoPoint {
+    let x = Scalar::from(x);
+    commitments
+        .iter()
+        .rev()
+        .fold(RistrettoPoint::default(), |acc, c| acc * x + c)
+}
+
+/// Shard numbers must be unique and non-zero
+fn check_shard_numbers(numbers: impl Iterator<Item = u32>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for n in numbers {
+        if n == 0 {
+            return Err(SsssErr::InvalidShardNumber(0));
+        }
+        if !seen.insert(n) {
+            return Err(SsssErr::DuplicateShard(n));
+        }
+    }
+    Ok(())
+}
+
+fn scalars_to_base64(scalars: &[Scalar]) -> Result<String> {
+    let bytes: Vec<u8> = scalars.iter().flat_map(|s| s.to_bytes()).collect();
+    base64_encode(&bytes)
+}
+
+fn scalars_from_base64(s: &str) -> Result<Vec<Scalar>> {
+    let bytes = base64_decode(s)?;
+    if !bytes.len().is_multiple_of(ELEMENT_LEN) {
+        return Err(SsssErr::MalformedShard("payload is not a whole number of scalars".into()));
+    }
+
+    bytes
+        .chunks_exact(ELEMENT_LEN)
+        .map(|chunk| {
+            let scalar = Scalar::from_canonical_bytes(chunk.try_into().unwrap());
+            Option::from(scalar).ok_or_else(|| SsssErr::MalformedShard("non-canonical scalar".into()))
+        })
+        .collect()
+}
+
+fn points_to_base64(points: &[RistrettoPoint]) -> Result<String> {
+    let bytes: Vec<u8> = points.iter().flat_map(|p| p.compress().to_bytes()).collect();
+    base64_encode(&bytes)
+}
+
+fn points_from_base64(s: &str) -> Result<Vec<RistrettoPoint>> {
+    let bytes = base64_decode(s)?;
+    if !bytes.len().is_multiple_of(ELEMENT_LEN) {
+        return Err(SsssErr::MalformedCommitments("payload is not a whole number of points".into()));
+    }
+
+    bytes
+        .chunks_exact(ELEMENT_LEN)
+        .map(|chunk| {
+            CompressedRistretto::from_slice(chunk)
+                .ok()
+                .and_then(|c| c.decompress())
+                .ok_or_else(|| SsssErr::MalformedCommitments("invalid group element".into()))
+        })
+        .collect()
+}
+
+fn parse_number<T: std::str::FromStr>(field: &str, err: fn(String) -> SsssErr) -> Result<T> {
+    field.parse().map_err(|_| err(format!("invalid number `{field}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::feldman::Feldman;
+    use super::pedersen::Pedersen;
+    use super::*;
+
+    fn split<S: Scheme>(threshold: u32, num_shards: u32, secret: &[u8]) -> (Commitments<S>, Vec<Shard<S>>) {
+        encode(&ShamirScheme::new(threshold, num_shards), secret).unwrap()
+    }
+
+    fn end_to_end<S: Scheme>() {
+        let secret: Vec<u8> = (0..70).collect();
+        let (commitments, shards) = split::<S>(3, 5, &secret);
+
+        for shard in &shards {
+            verify(&commitments, shard).unwrap();
+        }
+        assert_eq!(secret, decode(&commitments, &shards[0..3]).unwrap());
+        assert_eq!(secret, decode(&commitments, &shards[2..5]).unwrap());
+    }
+
+    fn bad_shards_are_pinpointed<S: Scheme>() {
+        let (commitments, mut shards) = split::<S>(2, 4, b"correct horse battery staple");
+        shards[1].values[0] += Scalar::ONE;
+        shards[3].values[0] = shards[2].values[0];
+
+        assert_eq!(Err(SsssErr::InvalidShards(vec![2])), verify(&commitments, &shards[1]));
+        assert_eq!(Err(SsssErr::InvalidShards(vec![2, 4])), decode(&commitments, &shards));
+        assert_eq!(b"correct horse battery staple".to_vec(), decode(&commitments, &[shards[2].clone(), shards[0].clone()]).unwrap());
+    }
+
+    fn shards_of_another_split_are_rejected<S: Scheme>() {
+        let (commitments, _) = split::<S>(2, 3, b"secret");
+        let (_, other) = split::<S>(2, 3, b"secret");
+
+        assert_eq!(Err(SsssErr::InvalidShards(vec![1, 2])), decode(&commitments, &other[0..2]));
+    }
+
+    fn too_few_shards<S: Scheme>() {
+        let (commitments, shards) = split::<S>(3, 5, b"secret");
+
+        assert_eq!(Err(SsssErr::InsufficientShards { needed: 3, got: 2 }), decode(&commitments, &shards[0..2]));
+    }
+
+    fn text_round_trip<S: Scheme>() {
+        let (commitments, shards) = split::<S>(2, 3, b"secret");
+
+        let parsed_commitments: Commitments<S> = commitments.to_string().parse().unwrap();
+        let parsed_shard: Shard<S> = shards[1].to_string().parse().unwrap();
+
+        assert_eq!(commitments, parsed_commitments);
+        assert_eq!(shards[1], parsed_shard);
+        assert!(matches!(format!("{}-2", S::SHARD_TAG).parse::<Shard<S>>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(format!("{}-6-3-AAAA", S::COMMITMENTS_TAG).parse::<Commitments<S>>(), Err(SsssErr::MalformedCommitments(_))));
+
+        // The secret length and threshold are untrusted
+        let huge = format!("{}-{}-255-AAAA", S::COMMITMENTS_TAG, usize::MAX);
+        assert!(matches!(huge.parse::<Commitments<S>>(), Err(SsssErr::MalformedCommitments(_))));
+    }
+
+    /// A `feldman` and a `pedersen` test for each check, in a module named after it
+    macro_rules! both_schemes {
+        ($($check:ident),* $(,)?) => {$(
+            mod $check {
+                #[test]
+                fn feldman() {
+                    super::$check::<super::Feldman>();
+                }
+
+                #[test]
+                fn pedersen() {
+                    super::$check::<super::Pedersen>();
+                }
+            }
+        )*};
+    }
+
+    both_schemes!(end_to_end, bad_shards_are_pinpointed, shards_of_another_split_are_rejected, too_few_shards, text_round_trip);
+
+    #[test]
+    fn more_than_255_shards() {
+        let secret = b"many custodians";
+        let (commitments, shards) = split::<Pedersen>(2, 300, secret);
+
+        verify(&commitments, &shards[299]).unwrap();
+        assert_eq!(300, shards[299].number());
+        assert_eq!(secret.to_vec(), decode(&commitments, &[shards[299].clone(), shards[3].clone()]).unwrap());
+    }
+
+    #[test]
+    fn tags_tell_the_schemes_apart() {
+        let (commitments, shards) = split::<Feldman>(2, 3, b"secret");
+
+        assert!(matches!(shards[0].to_string().parse::<Shard<Pedersen>>(), Err(SsssErr::MalformedShard(_))));
+        assert!(matches!(commitments.to_string().parse::<Commitments<Pedersen>>(), Err(SsssErr::MalformedCommitments(_))));
+    }
+
+    #[test]
+    fn chunking_round_trip() {
+        let secret: Vec<u8> = (0..100).collect();
+        let scalars = secret_to_scalars(&secret);
+
+        assert_eq!(4, scalars.len());
+        assert_eq!(num_chunks(secret.len()), scalars.len());
+        assert_eq!(secret, scalars_to_secret(&scalars, secret.len()));
+    }
+
+    #[test]
+    fn non_canonical_scalar() {
+        let encoded = base64_encode(&[0xff; ELEMENT_LEN]).unwrap();
+
+        assert!(matches!(scalars_from_base64(&encoded), Err(SsssErr::MalformedShard(_))));
+    }
+}
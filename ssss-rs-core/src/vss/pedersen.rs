@@ -0,0 +1,74 @@
+//! Pedersen's verifiable secret sharing
+//!
+//! Besides the secret polynomial `a` the dealer picks a random blinding polynomial `b`, and
+//! publishes `C_j = a_j * G + b_j * H` for each coefficient. Every shard carries both `a(x)` and
+//! `b(x)`, and is checked against `a(x) * G + b(x) * H == sum of x^j * C_j`. As nobody knows the
+//! discrete logarithm of `H` to base `G`, the commitments reveal nothing about the secret.
+
+use super::{Commitments, Scheme, Shard};
+use crate::math::{random_scalar, ScalarPoly};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+use std::sync::OnceLock;
+
+static GENERATOR_H: OnceLock<RistrettoPoint> = OnceLock::new();
+
+/// Second generator, derived by hashing so that its discrete logarithm is unknown
+fn generator_h() -> RistrettoPoint {
+    *GENERATOR_H.get_or_init(|| {
+        let hash: [u8; 64] = Sha512::digest(b"ssss-rs pedersen generator H").into();
+        RistrettoPoint::from_uniform_bytes(&hash)
+    })
+}
+
+/// Commits to the secret polynomial blinded by a random one; shards hold the values of both
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pedersen {}
+
+pub type PedersenShard = Shard<Pedersen>;
+pub type PedersenCommitments = Commitments<Pedersen>;
+
+impl Scheme for Pedersen {
+    const SHARD_TAG: &'static str = "p1";
+    const COMMITMENTS_TAG: &'static str = "pc1";
+    const VALUES_PER_CHUNK: usize = 2;
+
+    fn polynomials(chunk: Scalar, degree: usize) -> Vec<ScalarPoly> {
+        vec![ScalarPoly::random(chunk, degree), ScalarPoly::random(random_scalar(), degree)]
+    }
+
+    fn commit(scalars: &[Scalar]) -> RistrettoPoint {
+        RistrettoPoint::mul_base(&scalars[0]) + generator_h() * scalars[1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::err::SsssErr;
+    use crate::vss::{decode, encode};
+    use crate::ShamirScheme;
+
+    fn split(threshold: u32, num_shards: u32, secret: &[u8]) -> (PedersenCommitments, Vec<PedersenShard>) {
+        encode(&ShamirScheme::new(threshold, num_shards), secret).unwrap()
+    }
+
+    #[test]
+    fn blinding_value_is_checked_too() {
+        let (commitments, mut shards) = split(2, 3, b"secret");
+        // The second value of the first chunk is that of the blinding polynomial
+        shards[0].values[1] += Scalar::ONE;
+
+        assert_eq!(Err(SsssErr::InvalidShards(vec![1])), decode(&commitments, &shards));
+    }
+
+    #[test]
+    fn commitments_hide_the_secret() {
+        // Equal secrets give unrelated commitments, even for the constant term
+        let (first, _) = split(2, 3, b"secret");
+        let (second, _) = split(2, 3, b"secret");
+
+        assert_ne!(first.points[0], second.points[0]);
+    }
+}